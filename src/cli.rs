@@ -1,10 +1,18 @@
-use std::{path::PathBuf, str::FromStr};
+use std::{num::NonZeroUsize, path::PathBuf, str::FromStr};
 
 use clap::{ArgGroup, Parser, Subcommand};
 use git_testament::{git_testament, render_testament};
 use regex::bytes::Regex;
 
-use crate::{ValidationLevel, validators::LintMode};
+use crate::{
+    ValidationLevel,
+    commands::filter::{Motif, MotifDistance, NamesIndex, PairPolicy, PredicateKind},
+    fastq::fs::CompressionFormat,
+    validators::{
+        LintMode,
+        single::{Alphabet, DuplicateNameMode, QualityEncodingMode},
+    },
+};
 
 git_testament!(TESTAMENT);
 
@@ -52,28 +60,157 @@ pub enum Command {
     Subsample(SubsampleArgs),
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum DescribeFormat {
+    Json,
+    Tsv,
+}
+
 #[derive(Parser)]
 pub struct DescribeArgs {
-    /// FASTQ source.
+    /// Output format of the collected metrics.
+    #[arg(long, value_enum, default_value_t = DescribeFormat::Json)]
+    pub format: DescribeFormat,
+
+    /// The number of threads used to decompress BGZF sources.
+    #[arg(long, default_value_t = NonZeroUsize::MIN)]
+    pub threads: NonZeroUsize,
+
+    /// FASTQ source. Use `-` for stdin.
     pub src: PathBuf,
 }
 
 #[derive(Parser)]
-#[command(group(ArgGroup::new("filter").args(["names", "sequence_pattern"])))]
 pub struct FilterArgs {
     /// Allowlist of record names.
     #[arg(long)]
     pub names: Option<PathBuf>,
 
+    /// Denylist of record names. Records with names in this list are discarded.
+    #[arg(long)]
+    pub exclude_names: Option<PathBuf>,
+
+    /// How names lists are stored and searched.
+    ///
+    /// `sorted` and `bloom` require names files to be sorted bytewise, e.g., using `LC_ALL=C
+    /// sort`, and use much less memory than `memory` for large lists.
+    #[arg(long, value_enum, default_value_t = NamesIndex::Memory)]
+    pub names_index: NamesIndex,
+
+    /// Keep records that have definitions that match the given regular expression.
+    ///
+    /// The definition is the full header line, including the `@` prefix and any description, e.g.,
+    /// `--name-pattern :1101:` keeps records from tile 1101.
+    #[arg(long)]
+    pub name_pattern: Option<Regex>,
+
     /// Keep records that have sequences that match the given regular expression.
     #[arg(long)]
     pub sequence_pattern: Option<Regex>,
 
-    /// Filtered FASTQ destinations.
+    /// Keep records that have sequences that contain the given motif.
+    ///
+    /// The motif may include IUPAC ambiguity codes, e.g., `N` or `R`. Ambiguous bases in record
+    /// sequences never match.
+    #[arg(long)]
+    pub sequence_motif: Option<Motif>,
+
+    /// The maximum number of mismatches allowed when matching `--sequence-motif`.
+    #[arg(long, default_value_t = 0, requires = "sequence_motif")]
+    pub max_mismatches: usize,
+
+    /// How mismatches are counted when matching `--sequence-motif`.
+    #[arg(long, value_enum, default_value_t = MotifDistance::Hamming, requires = "sequence_motif")]
+    pub motif_distance: MotifDistance,
+
+    /// Also keep records that have sequences that contain the reverse complement of
+    /// `--sequence-motif`.
+    #[arg(long, requires = "sequence_motif")]
+    pub motif_reverse_complement: bool,
+
+    /// Keep records with a mean quality score of at least the given value.
+    ///
+    /// The mean is calculated from the mean error probability of the quality scores. Quality
+    /// scores are decoded as Phred+33.
+    #[arg(long)]
+    pub min_mean_quality: Option<f64>,
+
+    /// Keep records with at most the given number of expected errors.
+    ///
+    /// The number of expected errors is the sum of the error probabilities of the quality scores.
+    #[arg(long)]
+    pub max_expected_errors: Option<f64>,
+
+    /// Keep records where at least the given fraction [0.0, 1.0] of bases have a quality score at
+    /// or above `--base-quality-threshold`.
+    #[arg(long)]
+    pub min_base_quality_fraction: Option<f64>,
+
+    /// The base quality score threshold used by `--min-base-quality-fraction`.
+    #[arg(long, default_value_t = 20, requires = "min_base_quality_fraction")]
+    pub base_quality_threshold: u8,
+
+    /// Keep records with a sequence length of at least the given value.
+    #[arg(long)]
+    pub min_length: Option<usize>,
+
+    /// Keep records with a sequence length of at most the given value.
+    #[arg(long)]
+    pub max_length: Option<usize>,
+
+    /// How sequence length filters are applied to paired records.
+    #[arg(long, value_enum, default_value_t = PairPolicy::All)]
+    pub length_pair_policy: PairPolicy,
+
+    /// Keep records that match all of the given filters. This is the default.
+    #[arg(long, conflicts_with = "any")]
+    pub all: bool,
+
+    /// Keep records that match any of the given filters.
+    #[arg(long)]
+    pub any: bool,
+
+    /// Negate the result of a filter. Use multiple times to negate more than one.
+    #[arg(long, value_enum)]
+    pub invert: Vec<PredicateKind>,
+
+    /// Negate the result of the combined filters, i.e., keep the records that would otherwise be
+    /// discarded.
+    #[arg(long)]
+    pub invert_match: bool,
+
+    /// Compression format of the destinations.
+    ///
+    /// By default, this is chosen from the destination extension: `.gz` (gzip), `.bgz` (BGZF),
+    /// `.zst` (zstd), `.bz2` (bzip2), or `.xz` (xz). Other destinations, including stdout, are
+    /// uncompressed. zstd, bzip2, and xz require fq to be built with the feature of the same
+    /// name.
+    #[arg(long, value_enum)]
+    pub output_compression: Option<CompressionFormat>,
+
+    /// Compression level of the destinations.
+    ///
+    /// The valid range depends on the format: 0-9 for gzip, BGZF, and xz; 1-9 for bzip2; and
+    /// 1-22 for zstd. By default, the default level of the format is used.
+    #[arg(long)]
+    pub compression_level: Option<u32>,
+
+    /// The number of threads used to decompress BGZF sources and compress BGZF destinations.
+    #[arg(long, default_value_t = NonZeroUsize::MIN)]
+    pub threads: NonZeroUsize,
+
+    /// Filtered FASTQ destinations. Use `-` for stdout.
     #[arg(long, required = true)]
     pub dsts: Vec<PathBuf>,
 
-    /// FASTQ sources. Accepts both raw and gzipped FASTQ inputs.
+    /// Rejected FASTQ destinations.
+    ///
+    /// Records that do not pass the filters are written here. If set, there must be one for each
+    /// source.
+    #[arg(long)]
+    pub rejected_dsts: Vec<PathBuf>,
+
+    /// FASTQ sources. Accepts both raw and compressed FASTQ inputs. Use `-` for stdin.
     pub srcs: Vec<PathBuf>,
 }
 
@@ -95,6 +232,39 @@ pub struct LintArgs {
     #[arg(long)]
     pub disable_validator: Vec<String>,
 
+    /// Enable opt-in validators by code, e.g., S009 or P002. Use multiple times to enable more
+    /// than one. Codes of unknown or default validators are rejected.
+    #[arg(long)]
+    pub enable_validator: Vec<String>,
+
+    /// The valid sequence characters for S002 (AlphabetValidator).
+    ///
+    /// This is either a preset name or a list of characters, e.g., `ACGTacgt`. The presets are
+    /// `dna` (ACGTN), `dna-strict` (ACGT), `dna-iupac` (ACGT and the IUPAC ambiguity codes), and
+    /// `rna` (ACGUN). Preset names are case-insensitive, and preset alphabets accept both upper
+    /// and lowercase letters. A list of characters is case-sensitive.
+    #[arg(long, default_value = "dna")]
+    pub alphabet: Alphabet,
+
+    /// Check quality scores against a quality score encoding using S008
+    /// (QualityEncodingValidator).
+    ///
+    /// With `auto`, no encoding is checked. Instead, the encodings that are consistent with the
+    /// range of observed quality scores are logged and included in the report.
+    #[arg(long, value_enum)]
+    pub quality_encoding: Option<QualityEncodingMode>,
+
+    /// How S007 (DuplicateNameValidator) finds possible duplicate names.
+    ///
+    /// `bloom` uses an in-memory Bloom filter that grows with the number of records. `exact`
+    /// spills sorted runs of name hashes to temporary files and merges them, at most 64 at a
+    /// time, so the memory for name hashes is bounded. Set `TMPDIR` to change where temporary
+    /// files are written. In both modes, possible duplicates are held in memory and checked
+    /// against the full names in a second pass over read 1, so memory still grows with the number
+    /// of duplicated names.
+    #[arg(long, value_enum, default_value_t = DuplicateNameMode::Bloom)]
+    pub duplicate_name_mode: DuplicateNameMode,
+
     /// Define a record definition separator.
     ///
     /// This is used to strip the description from a record name.
@@ -103,10 +273,31 @@ pub struct LintArgs {
     #[arg(long)]
     pub record_definition_separator: Option<AsciiChar>,
 
-    /// Read 1 source. Accepts both raw and gzipped FASTQ inputs.
+    /// Write a JSON report of the validation results to the given path. Use `-` for stdout.
+    ///
+    /// The report includes the validation errors (see `report-max-errors`), failure counts per
+    /// validator, record counts per source, the enabled validators, and whether the sources
+    /// passed. If linting stops early, e.g., on an I/O error, a partial report that does not pass
+    /// is still written.
+    #[arg(long)]
+    pub report: Option<PathBuf>,
+
+    /// The maximum number of validation errors included in the report. Use 0 for no limit.
+    ///
+    /// Errors past this limit are still counted in the failure counts and `errors_omitted`.
+    #[arg(long, default_value_t = 1000, requires = "report")]
+    pub report_max_errors: usize,
+
+    /// The number of threads used to decompress BGZF sources and validate records.
+    ///
+    /// Records are validated in batches, and errors are still reported in record order.
+    #[arg(long, default_value_t = NonZeroUsize::MIN)]
+    pub threads: NonZeroUsize,
+
+    /// Read 1 source. Accepts both raw and compressed FASTQ inputs. Use `-` for stdin.
     pub r1_src: PathBuf,
 
-    /// Read 2 source. Accepts both raw and gzipped FASTQ inputs.
+    /// Read 2 source. Accepts both raw and compressed FASTQ inputs. Use `-` for stdin.
     pub r2_src: Option<PathBuf>,
 }
 
@@ -114,33 +305,111 @@ pub struct LintArgs {
 #[command(group(
     ArgGroup::new("quantity")
         .required(true)
-        .args(["probability", "record_count"])
+        .args(["probability", "record_count", "target_bases", "target_coverage"])
 ))]
 pub struct SubsampleArgs {
     /// The probability a record is kept, as a percentage (0.0, 1.0). Cannot be used with
     /// `record-count`.
+    ///
+    /// Use multiple times to generate a subsample replicate for each probability.
     #[arg(short, long)]
-    pub probability: Option<f64>,
+    pub probability: Vec<f64>,
 
     /// The exact number of records to keep. Cannot be used with `probability`.
+    ///
+    /// Use multiple times to generate a subsample replicate for each count. Replicates are
+    /// sampled in a single pass, holding the sampled records in memory.
     #[arg(short = 'n', long)]
-    pub record_count: Option<u64>,
+    pub record_count: Vec<u64>,
+
+    /// Sample replicates as nested subsets, i.e., each smaller replicate is a subset of every
+    /// larger replicate. By default, replicates are sampled independently.
+    #[arg(long, conflicts_with_all = ["target_bases", "target_coverage"])]
+    pub nested: bool,
+
+    /// Sample `record-count` records in a single pass using reservoir sampling.
+    ///
+    /// Sampled records are held in memory until all sources are read. This does not reread the
+    /// sources, so it also works with non-seekable inputs, e.g., stdin (`-`).
+    #[arg(long, conflicts_with_all = ["probability", "target_bases", "target_coverage"])]
+    pub reservoir: bool,
+
+    /// The number of bases to keep.
+    ///
+    /// Whole records (or pairs) are randomly selected until their total number of bases is at
+    /// least the given value. The first input (and second, if paired) is read twice.
+    ///
+    /// The base count of every record (or pair) is held in memory, using 4 bytes per record,
+    /// e.g., about 4 GB per billion records. Use `approximate` for constant memory.
+    #[arg(long)]
+    pub target_bases: Option<u64>,
+
+    /// The depth of coverage to keep, i.e., `target-bases` = `target-coverage` * `genome-size`.
+    ///
+    /// This has the same memory cost as `target-bases`.
+    #[arg(long, requires = "genome_size")]
+    pub target_coverage: Option<f64>,
+
+    /// The genome size, in bases, used to calculate the number of bases to keep from
+    /// `target-coverage`.
+    #[arg(long, requires = "target_coverage")]
+    pub genome_size: Option<u64>,
+
+    /// Keep each record (or pair) with probability `target-bases` / total bases instead of
+    /// selecting records until the target is met.
+    ///
+    /// This only counts the total number of bases in the first pass, so it uses constant memory,
+    /// but the number of bases kept is only close to the target.
+    #[arg(long, conflicts_with_all = ["probability", "record_count"])]
+    pub approximate: bool,
 
     /// Seed to use for the random number generator.
     #[arg(short, long)]
     pub seed: Option<u64>,
 
-    /// Read 1 destination. Output will be gzipped if ends in `.gz`.
+    /// Decide whether to keep a record using a hash of its name and the seed.
+    ///
+    /// Record names are compared without their descriptions and read numbers, e.g., `/1`, so
+    /// mates and any other copies of a record are always sampled identically. The seed defaults
+    /// to 0.
+    #[arg(long, conflicts_with_all = ["record_count", "target_bases", "target_coverage"])]
+    pub hash_by_name: bool,
+
+    /// Compression format of the destinations.
+    ///
+    /// By default, this is chosen from the destination extension: `.gz` (gzip), `.bgz` (BGZF),
+    /// `.zst` (zstd), `.bz2` (bzip2), or `.xz` (xz). Other destinations, including stdout, are
+    /// uncompressed. zstd, bzip2, and xz require fq to be built with the feature of the same
+    /// name.
+    #[arg(long, value_enum)]
+    pub output_compression: Option<CompressionFormat>,
+
+    /// Compression level of the destinations.
+    ///
+    /// The valid range depends on the format: 0-9 for gzip, BGZF, and xz; 1-9 for bzip2; and
+    /// 1-22 for zstd. By default, the default level of the format is used.
     #[arg(long)]
-    pub r1_dst: PathBuf,
+    pub compression_level: Option<u32>,
 
-    /// Read 2 destination. Output will be gzipped if ends in `.gz`.
+    /// The number of threads used to decompress BGZF sources and compress BGZF destinations.
+    #[arg(long, default_value_t = NonZeroUsize::MIN)]
+    pub threads: NonZeroUsize,
+
+    /// Read 1 destination. Use `-` for stdout.
+    ///
+    /// When generating replicates, use once for each replicate, in the same order.
+    #[arg(long, required = true)]
+    pub r1_dst: Vec<PathBuf>,
+
+    /// Read 2 destination. Use `-` for stdout.
+    ///
+    /// When generating replicates, use once for each replicate, in the same order.
     #[arg(long)]
-    pub r2_dst: Option<PathBuf>,
+    pub r2_dst: Vec<PathBuf>,
 
-    /// Read 1 source. Accepts both raw and gzipped FASTQ inputs.
+    /// Read 1 source. Accepts both raw and compressed FASTQ inputs. Use `-` for stdin.
     pub r1_src: PathBuf,
 
-    /// Read 2 source. Accepts both raw and gzipped FASTQ inputs.
+    /// Read 2 source. Accepts both raw and compressed FASTQ inputs. Use `-` for stdin.
     pub r2_src: Option<PathBuf>,
 }
@@ -1,3 +1,4 @@
 mod bloom_filter;
+mod external_hash_sorter;
 
-pub use self::bloom_filter::ScalableBloomFilter;
+pub use self::{bloom_filter::ScalableBloomFilter, external_hash_sorter::ExternalHashSorter};
@@ -0,0 +1,257 @@
+//! External sorter for 64-bit hashes.
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, Write},
+    mem,
+};
+
+// The maximum number of runs merged at once.
+const MAX_FAN_IN: usize = 64;
+
+/// A multiset of 64-bit hashes that uses bounded memory.
+///
+/// Hashes are held in memory until a run is full. Full runs are sorted and spilled to temporary
+/// files. Runs are merged at most 64 at a time, so the number of open files stays small: when a
+/// level has 64 runs, they are merged into a single run on the next level. The final runs are
+/// merged to find hashes that were inserted more than once.
+pub struct ExternalHashSorter {
+    buf: Vec<u64>,
+    run_capacity: usize,
+    fan_in: usize,
+    levels: Vec<Vec<File>>,
+}
+
+impl ExternalHashSorter {
+    /// Creates an external hash sorter that holds up to `run_capacity` hashes in memory.
+    ///
+    /// Runs are written to unnamed temporary files in the default temporary directory, which can
+    /// be set using `TMPDIR`.
+    pub fn new(run_capacity: usize) -> Self {
+        Self::with_fan_in(run_capacity, MAX_FAN_IN)
+    }
+
+    fn with_fan_in(run_capacity: usize, fan_in: usize) -> Self {
+        assert!(run_capacity > 0, "run capacity must be > 0");
+        assert!(fan_in > 1, "fan-in must be > 1");
+
+        Self {
+            buf: Vec::new(),
+            run_capacity,
+            fan_in,
+            levels: Vec::new(),
+        }
+    }
+
+    /// Adds a hash.
+    pub fn insert(&mut self, hash: u64) -> io::Result<()> {
+        self.buf.push(hash);
+
+        if self.buf.len() >= self.run_capacity {
+            self.spill()?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of runs on disk.
+    pub fn run_count(&self) -> usize {
+        self.levels.iter().map(Vec::len).sum()
+    }
+
+    /// Returns the set of hashes that were inserted more than once.
+    ///
+    /// The set is held in memory, so it grows with the number of distinct duplicated hashes.
+    pub fn duplicates(mut self) -> io::Result<HashSet<u64>> {
+        let mut duplicates = HashSet::new();
+
+        if self.levels.is_empty() {
+            self.buf.sort_unstable();
+            add_duplicates(&mut duplicates, self.buf.iter().copied().map(Ok))?;
+            return Ok(duplicates);
+        }
+
+        self.spill()?;
+
+        let mut runs: Vec<File> = self.levels.into_iter().flatten().collect();
+
+        while runs.len() > self.fan_in {
+            let rest = runs.split_off(self.fan_in);
+            let run = write_run(Merge::new(runs)?)?;
+            runs = rest;
+            runs.push(run);
+        }
+
+        add_duplicates(&mut duplicates, Merge::new(runs)?)?;
+
+        Ok(duplicates)
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+
+        self.buf.sort_unstable();
+        let run = write_run(self.buf.iter().copied().map(Ok))?;
+        self.buf.clear();
+
+        self.push_run(0, run)
+    }
+
+    // Adds a run to a level, merging the level into the next when it is full.
+    fn push_run(&mut self, level: usize, run: File) -> io::Result<()> {
+        if self.levels.len() <= level {
+            self.levels.push(Vec::new());
+        }
+
+        self.levels[level].push(run);
+
+        if self.levels[level].len() >= self.fan_in {
+            let runs = mem::take(&mut self.levels[level]);
+            let run = write_run(Merge::new(runs)?)?;
+            self.push_run(level + 1, run)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Writes a sorted stream of hashes to a temporary file and rewinds it.
+fn write_run<I>(hashes: I) -> io::Result<File>
+where
+    I: IntoIterator<Item = io::Result<u64>>,
+{
+    let mut writer = tempfile::tempfile().map(BufWriter::new)?;
+
+    for result in hashes {
+        writer.write_all(&result?.to_le_bytes())?;
+    }
+
+    let mut file = writer.into_inner().map_err(|e| e.into_error())?;
+    file.rewind()?;
+
+    Ok(file)
+}
+
+// A k-way merge of sorted runs.
+struct Merge {
+    readers: Vec<BufReader<File>>,
+    heap: BinaryHeap<Reverse<(u64, usize)>>,
+}
+
+impl Merge {
+    fn new(runs: Vec<File>) -> io::Result<Self> {
+        let mut readers: Vec<_> = runs.into_iter().map(BufReader::new).collect();
+        let mut heap = BinaryHeap::with_capacity(readers.len());
+
+        for (i, reader) in readers.iter_mut().enumerate() {
+            if let Some(hash) = read_hash(reader)? {
+                heap.push(Reverse((hash, i)));
+            }
+        }
+
+        Ok(Self { readers, heap })
+    }
+}
+
+impl Iterator for Merge {
+    type Item = io::Result<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((hash, i)) = self.heap.pop()?;
+
+        match read_hash(&mut self.readers[i]) {
+            Ok(Some(next_hash)) => self.heap.push(Reverse((next_hash, i))),
+            Ok(None) => {}
+            Err(e) => return Some(Err(e)),
+        }
+
+        Some(Ok(hash))
+    }
+}
+
+// Adds hashes that occur more than once in a sorted stream.
+fn add_duplicates<I>(duplicates: &mut HashSet<u64>, hashes: I) -> io::Result<()>
+where
+    I: IntoIterator<Item = io::Result<u64>>,
+{
+    let mut prev_hash = None;
+
+    for result in hashes {
+        let hash = result?;
+
+        if prev_hash == Some(hash) {
+            duplicates.insert(hash);
+        }
+
+        prev_hash = Some(hash);
+    }
+
+    Ok(())
+}
+
+fn read_hash<R>(reader: &mut R) -> io::Result<Option<u64>>
+where
+    R: Read,
+{
+    let mut buf = [0; 8];
+
+    match reader.read_exact(&mut buf) {
+        Ok(()) => Ok(Some(u64::from_le_bytes(buf))),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicates() -> io::Result<()> {
+        let hashes = [8, 3, 5, 3, 13, 21, 1, 8, 2, 1, 8];
+        let expected = HashSet::from([1, 3, 8]);
+
+        for run_capacity in [1, 2, 4, 100] {
+            let mut sorter = ExternalHashSorter::new(run_capacity);
+
+            for hash in hashes {
+                sorter.insert(hash)?;
+            }
+
+            assert_eq!(sorter.run_count(), hashes.len() / run_capacity);
+            assert_eq!(sorter.duplicates()?, expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicates_with_multiple_merge_passes() -> io::Result<()> {
+        let hashes = [8, 3, 5, 3, 13, 21, 1, 8, 2, 1, 8];
+        let expected = HashSet::from([1, 3, 8]);
+
+        for fan_in in [2, 3, 4] {
+            let mut sorter = ExternalHashSorter::with_fan_in(1, fan_in);
+
+            for hash in hashes {
+                sorter.insert(hash)?;
+            }
+
+            assert!(sorter.levels.iter().all(|runs| runs.len() < fan_in));
+            assert_eq!(sorter.duplicates()?, expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicates_with_no_hashes() -> io::Result<()> {
+        let sorter = ExternalHashSorter::new(4);
+        assert!(sorter.duplicates()?.is_empty());
+        Ok(())
+    }
+}
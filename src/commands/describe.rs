@@ -1,17 +1,23 @@
-use std::io;
+use std::io::{self, BufWriter, Write};
 
+use serde::{Serialize, Serializer, ser::SerializeMap};
+use serde_json::Value;
 use tracing::info;
 
 use crate::{
-    cli::DescribeArgs,
-    fastq::{self, Record},
-    metrics,
+    cli::{DescribeArgs, DescribeFormat},
+    fastq::{self, Record, fs::ReadOptions},
+    metrics::{self, Metric},
 };
 
 pub fn describe(args: DescribeArgs) -> io::Result<()> {
     info!(command = "describe", "fq");
 
-    let mut reader = fastq::fs::open(args.src)?;
+    let options = ReadOptions {
+        threads: args.threads,
+    };
+
+    let mut reader = fastq::fs::open_with_options(args.src, options)?;
     let mut record = Record::default();
 
     let mut metrics = metrics::default();
@@ -22,11 +28,136 @@ pub fn describe(args: DescribeArgs) -> io::Result<()> {
         }
     }
 
-    for metric in &metrics {
-        metric.println();
+    let stdout = io::stdout().lock();
+    let mut writer = BufWriter::new(stdout);
+
+    match args.format {
+        DescribeFormat::Json => write_json(&mut writer, &metrics)?,
+        DescribeFormat::Tsv => write_tsv(&mut writer, &metrics)?,
     }
 
+    writer.flush()?;
+
     info!("done");
 
     Ok(())
 }
+
+struct Report<'a>(&'a [Box<dyn Metric>]);
+
+impl Serialize for Report<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+
+        for metric in self.0 {
+            map.serialize_entry(metric.name(), &metric.value())?;
+        }
+
+        map.end()
+    }
+}
+
+fn write_json<W>(writer: &mut W, metrics: &[Box<dyn Metric>]) -> io::Result<()>
+where
+    W: Write,
+{
+    serde_json::to_writer_pretty(&mut *writer, &Report(metrics))?;
+    writeln!(writer)
+}
+
+fn write_tsv<W>(writer: &mut W, metrics: &[Box<dyn Metric>]) -> io::Result<()>
+where
+    W: Write,
+{
+    for metric in metrics {
+        write!(writer, "{}\t", metric.name())?;
+        write_tsv_value(writer, &metric.value())?;
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+// Arrays are written as comma-separated values, and null values are written as empty fields.
+fn write_tsv_value<W>(writer: &mut W, value: &Value) -> io::Result<()>
+where
+    W: Write,
+{
+    match value {
+        Value::Null => Ok(()),
+        Value::Array(values) => {
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    write!(writer, ",")?;
+                }
+
+                write_tsv_value(writer, value)?;
+            }
+
+            Ok(())
+        }
+        _ => write!(writer, "{value}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_metrics() -> io::Result<Vec<Box<dyn Metric>>> {
+        let mut metrics = metrics::default();
+
+        for record in [
+            Record::new("@r0", "ACGT", "+", "5555"),
+            Record::new("@r1", "AC", "+", "55"),
+        ] {
+            for metric in &mut metrics {
+                metric.visit(&record)?;
+            }
+        }
+
+        Ok(metrics)
+    }
+
+    #[test]
+    fn test_write_json() -> io::Result<()> {
+        let metrics = build_metrics()?;
+
+        let mut buf = Vec::new();
+        write_json(&mut buf, &metrics)?;
+
+        let actual: Value = serde_json::from_slice(&buf)?;
+        let expected = serde_json::json!({
+            "record_count": 2,
+            "min_sequence_length": 2,
+            "max_sequence_length": 4,
+            "avg_quality_score_per_position": [20.0, 20.0, 20.0, 20.0],
+        });
+
+        assert_eq!(actual, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_tsv() -> io::Result<()> {
+        let metrics = build_metrics()?;
+
+        let mut buf = Vec::new();
+        write_tsv(&mut buf, &metrics)?;
+
+        let expected = b"\
+record_count\t2
+min_sequence_length\t2
+max_sequence_length\t4
+avg_quality_score_per_position\t20.0,20.0,20.0,20.0
+";
+
+        assert_eq!(buf, expected);
+
+        Ok(())
+    }
+}
@@ -1,104 +1,173 @@
+mod length;
+mod name_pattern;
+mod names;
+mod predicate;
+mod quality;
+mod sequence_motif;
+mod sequence_pattern;
+
 use std::{
-    collections::HashSet,
     fs::File,
     io::{self, BufRead, BufReader, Write},
     path::{Path, PathBuf},
 };
 
-use regex::bytes::Regex;
 use thiserror::Error;
 use tracing::info;
 
-use crate::{cli::FilterArgs, fastq};
+pub use self::{
+    length::PairPolicy,
+    names::NamesIndex,
+    predicate::PredicateKind,
+    sequence_motif::{Motif, MotifDistance},
+};
+
+use self::{
+    length::LengthFilter,
+    name_pattern::NamePatternFilter,
+    names::{NameSet, NamesFilter, SortedNames, read_names},
+    predicate::{Combinator, Expression, Predicate},
+    quality::QualityFilter,
+    sequence_motif::SequenceMotifFilter,
+    sequence_pattern::SequencePatternFilter,
+};
+use crate::{
+    cli::FilterArgs,
+    collections::ScalableBloomFilter,
+    fastq::{
+        self,
+        fs::{ReadOptions, WriteOptions},
+    },
+};
+
+const FALSE_POSITIVE_PROBABILITY: f64 = 0.0001;
+const INITIAL_CAPACITY: usize = 10_000_000;
 
+/// Filters sets of records from `readers` into `writers`.
+///
+/// Sets of records that do not match are written to `rejected_writers`, if any.
+///
+/// This returns the number of sets kept and the total number of sets read.
 fn _filter<R, W, F>(
     readers: &mut [fastq::io::Reader<R>],
     writers: &mut [fastq::io::Writer<W>],
+    rejected_writers: &mut [fastq::io::Writer<W>],
     filter: F,
-) -> io::Result<()>
+) -> io::Result<(u64, u64)>
 where
     R: BufRead,
     W: Write,
-    F: Fn(&fastq::Record) -> bool,
+    F: Fn(&[fastq::Record]) -> io::Result<bool>,
 {
-    let mut record = fastq::Record::default();
-    let mut is_match = false;
-    let mut is_eof = false;
-
-    while !is_eof {
-        for (i, (reader, writer)) in readers.iter_mut().zip(writers.iter_mut()).enumerate() {
-            if i == 0 {
-                if reader.read_record(&mut record)? == 0 {
-                    is_eof = true;
-                    break;
+    let mut records = vec![fastq::Record::default(); readers.len()];
+
+    let mut n = 0;
+    let mut total = 0;
+
+    loop {
+        for (i, (reader, record)) in readers.iter_mut().zip(records.iter_mut()).enumerate() {
+            if reader.read_record(record)? == 0 {
+                if i == 0 {
+                    return Ok((n, total));
+                } else {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
                 }
-
-                is_match = filter(&record);
-            } else if reader.read_record(&mut record)? == 0 {
-                return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
             }
+        }
 
-            if is_match {
-                writer.write_record(&record)?;
-            }
+        let writers = if filter(&records)? {
+            n += 1;
+            &mut *writers
+        } else {
+            &mut *rejected_writers
+        };
+
+        for (writer, record) in writers.iter_mut().zip(&records) {
+            writer.write_record(record)?;
         }
-    }
 
-    Ok(())
+        total += 1;
+    }
 }
 
 fn copy_filtered<R, W>(
     readers: &mut [fastq::io::Reader<R>],
-    names: &HashSet<Vec<u8>>,
+    expression: &Expression,
     writers: &mut [fastq::io::Writer<W>],
-) -> io::Result<()>
+    rejected_writers: &mut [fastq::io::Writer<W>],
+) -> io::Result<(u64, u64)>
 where
     R: BufRead,
     W: Write,
 {
-    _filter(readers, writers, |record| {
-        let id = name_id(record.name());
-        names.contains(id)
+    _filter(readers, writers, rejected_writers, |records| {
+        expression.is_match(records)
     })
 }
 
-fn read_names<R>(reader: R) -> io::Result<HashSet<Vec<u8>>>
-where
-    R: BufRead,
-{
-    reader
-        .lines()
-        .map(|res| res.map(|line| line.into_bytes()))
-        .collect()
-}
+pub fn filter(args: FilterArgs) -> Result<(), FilterError> {
+    let srcs = &args.srcs;
+    let dsts = &args.dsts;
+    let rejected_dsts = &args.rejected_dsts;
 
-// Names always begin with an `@` character.
-const ID_START_OFFSET: usize = 1;
+    info!(command = "filter", "fq");
 
-fn name_id(name: &[u8]) -> &[u8] {
-    let pos = name.iter().rev().position(|&b| b == b'/' || b == b' ');
+    ensure_single_stdio(srcs, dsts, rejected_dsts)?;
 
-    if let Some(i) = pos {
-        let len = name.len();
-        let end = len - i - 1;
-        &name[ID_START_OFFSET..end]
-    } else {
-        &name[ID_START_OFFSET..]
+    if !rejected_dsts.is_empty() && rejected_dsts.len() != srcs.len() {
+        return Err(FilterError::InvalidRejectedDestinationCount {
+            expected: srcs.len(),
+            actual: rejected_dsts.len(),
+        });
     }
-}
 
-pub fn filter(args: FilterArgs) -> Result<(), FilterError> {
-    let srcs = &args.srcs;
-    let dsts = &args.dsts;
+    let expression = build_expression(&args)?;
 
-    info!(command = "filter", "fq");
+    if expression.is_empty() && args.invert_match {
+        return Err(FilterError::MissingFilter("--invert-match"));
+    } else if expression.is_empty() && !rejected_dsts.is_empty() {
+        return Err(FilterError::MissingFilter("--rejected-dsts"));
+    }
 
-    if let Some(names_src) = args.names.as_ref() {
-        filter_by_names(srcs, dsts, names_src)?;
-    } else if let Some(sequence_pattern) = args.sequence_pattern.as_ref() {
-        filter_by_sequence_pattern(srcs, dsts, sequence_pattern)?;
+    let read_options = ReadOptions {
+        threads: args.threads,
+    };
+
+    let write_options = WriteOptions {
+        compression: args.output_compression,
+        compression_level: args.compression_level,
+        threads: args.threads,
+    };
+
+    if expression.is_empty() {
+        cat(srcs, dsts, read_options, write_options)?;
     } else {
-        cat(srcs, dsts)?;
+        let mut readers = build_readers(srcs, read_options)?;
+        let mut writers = build_writers(dsts, write_options)?;
+        let mut rejected_writers = build_writers(rejected_dsts, write_options)?;
+
+        info!("filtering fastq where {expression}");
+
+        let (n, total) = copy_filtered(
+            &mut readers,
+            &expression,
+            &mut writers,
+            &mut rejected_writers,
+        )?;
+
+        let percentage = if total == 0 {
+            0.0
+        } else {
+            (n as f64) / (total as f64) * 100.0
+        };
+
+        info!(
+            "kept {}/{} ({:.1}%) records; rejected {} records",
+            n,
+            total,
+            percentage,
+            total - n
+        );
     }
 
     info!("done");
@@ -106,103 +175,191 @@ pub fn filter(args: FilterArgs) -> Result<(), FilterError> {
     Ok(())
 }
 
-fn cat<P, Q>(srcs: &[P], dsts: &[Q]) -> io::Result<()>
-where
-    P: AsRef<Path>,
-    Q: AsRef<Path>,
-{
-    for (src, dst) in srcs.iter().zip(dsts) {
-        let mut reader = File::open(src)?;
-        let mut writer = File::create(dst)?;
-        io::copy(&mut reader, &mut writer)?;
+fn is_enabled(args: &FilterArgs, kind: PredicateKind) -> bool {
+    match kind {
+        PredicateKind::Names => args.names.is_some(),
+        PredicateKind::ExcludeNames => args.exclude_names.is_some(),
+        PredicateKind::NamePattern => args.name_pattern.is_some(),
+        PredicateKind::SequencePattern => args.sequence_pattern.is_some(),
+        PredicateKind::SequenceMotif => args.sequence_motif.is_some(),
+        PredicateKind::Quality => {
+            args.min_mean_quality.is_some()
+                || args.max_expected_errors.is_some()
+                || args.min_base_quality_fraction.is_some()
+        }
+        PredicateKind::Length => args.min_length.is_some() || args.max_length.is_some(),
     }
-
-    Ok(())
 }
 
-fn filter_by_names<P, Q, R>(srcs: &[P], dsts: &[Q], names_src: R) -> Result<(), FilterError>
-where
-    P: AsRef<Path>,
-    Q: AsRef<Path>,
-    R: AsRef<Path>,
-{
-    info!("reading names");
+fn build_expression(args: &FilterArgs) -> Result<Expression, FilterError> {
+    if let Some(&kind) = args.invert.iter().find(|&&kind| !is_enabled(args, kind)) {
+        return Err(FilterError::InvalidInversion(kind));
+    }
+
+    if let Some(fraction) = args.min_base_quality_fraction
+        && !(0.0..=1.0).contains(&fraction)
+    {
+        return Err(FilterError::InvalidFraction(fraction));
+    }
 
-    let names_src = names_src.as_ref();
+    let combinator = if args.any {
+        Combinator::Any
+    } else {
+        Combinator::All
+    };
 
-    let reader = File::open(names_src)
-        .map(BufReader::new)
-        .map_err(|e| FilterError::OpenFile(e, names_src.into()))?;
-    let names = read_names(reader).map_err(FilterError::ReadNames)?;
+    let mut expression = Expression::new(combinator);
+    expression.set_inverted(args.invert_match);
 
-    info!("read {} names", names.len());
-    info!("filtering fastq");
+    let mut push = |predicate: Box<dyn Predicate>| {
+        let is_inverted = args.invert.contains(&predicate.kind());
+        expression.push(predicate, is_inverted);
+    };
+
+    if let Some(names_src) = args.names.as_ref() {
+        let names = read_names_file(names_src, args.names_index)?;
+        push(Box::new(NamesFilter::new(names)));
+    }
 
-    let mut readers = build_readers(srcs)?;
-    let mut writers = build_writers(dsts)?;
+    if let Some(names_src) = args.exclude_names.as_ref() {
+        let names = read_names_file(names_src, args.names_index)?;
+        push(Box::new(NamesFilter::excluding(names)));
+    }
 
-    copy_filtered(&mut readers, &names, &mut writers)?;
+    if let Some(name_pattern) = args.name_pattern.as_ref() {
+        push(Box::new(NamePatternFilter::new(name_pattern.clone())));
+    }
 
-    Ok(())
+    if let Some(sequence_pattern) = args.sequence_pattern.as_ref() {
+        push(Box::new(SequencePatternFilter::new(
+            sequence_pattern.clone(),
+        )));
+    }
+
+    if let Some(sequence_motif) = args.sequence_motif.as_ref() {
+        push(Box::new(SequenceMotifFilter::new(
+            sequence_motif.clone(),
+            args.motif_distance,
+            args.max_mismatches,
+            args.motif_reverse_complement,
+        )));
+    }
+
+    if is_enabled(args, PredicateKind::Quality) {
+        push(Box::new(QualityFilter::new(
+            args.min_mean_quality,
+            args.max_expected_errors,
+            args.min_base_quality_fraction
+                .map(|fraction| (args.base_quality_threshold, fraction)),
+        )));
+    }
+
+    if is_enabled(args, PredicateKind::Length) {
+        push(Box::new(LengthFilter::new(
+            args.min_length,
+            args.max_length,
+            args.length_pair_policy,
+        )));
+    }
+
+    Ok(expression)
 }
 
-fn copy_filtered_by_sequence_pattern<R, W>(
-    readers: &mut [fastq::io::Reader<R>],
-    sequence_pattern: &Regex,
-    writers: &mut [fastq::io::Writer<W>],
-) -> io::Result<()>
-where
-    R: BufRead,
-    W: Write,
-{
-    _filter(readers, writers, |record| {
-        sequence_pattern.is_match(record.sequence())
-    })
+fn read_names_file(src: &Path, index: NamesIndex) -> Result<NameSet, FilterError> {
+    info!("reading names");
+
+    let file = File::open(src).map_err(|e| FilterError::OpenFile(e, src.into()))?;
+
+    let names = match index {
+        NamesIndex::Memory => read_names(BufReader::new(file)).map(NameSet::Memory),
+        NamesIndex::Sorted => SortedNames::read(file, |_| {}).map(NameSet::Sorted),
+        NamesIndex::Bloom => {
+            let mut filter = ScalableBloomFilter::new(FALSE_POSITIVE_PROBABILITY, INITIAL_CAPACITY);
+
+            SortedNames::read(file, |name| {
+                filter.insert(name);
+            })
+            .map(|names| NameSet::Bloom(filter, names))
+        }
+    }
+    .map_err(FilterError::ReadNames)?;
+
+    info!("read {} names", names.len());
+
+    Ok(names)
 }
 
-fn filter_by_sequence_pattern<P, Q>(
+fn cat<P, Q>(
     srcs: &[P],
     dsts: &[Q],
-    sequence_pattern: &Regex,
+    read_options: ReadOptions,
+    write_options: WriteOptions,
 ) -> Result<(), FilterError>
 where
     P: AsRef<Path>,
     Q: AsRef<Path>,
 {
-    let mut readers = build_readers(srcs)?;
-    let mut writers = build_writers(dsts)?;
+    for (src, dst) in srcs.iter().zip(dsts) {
+        let (src, dst) = (src.as_ref(), dst.as_ref());
 
-    info!("filtering fastq where sequence matches `{sequence_pattern}`");
+        let mut reader = fastq::fs::open_decompressed(src, read_options)
+            .map_err(|e| FilterError::OpenFile(e, src.into()))?;
+        let mut writer = fastq::fs::create_compressed(dst, write_options)
+            .map_err(|e| FilterError::CreateFile(e, dst.into()))?;
 
-    copy_filtered_by_sequence_pattern(&mut readers, sequence_pattern, &mut writers)?;
+        io::copy(&mut reader, &mut writer)?;
+        writer.flush()?;
+    }
 
     Ok(())
 }
 
-fn build_readers<P>(srcs: &[P]) -> Result<Vec<fastq::io::Reader<Box<dyn BufRead>>>, FilterError>
+fn build_readers<P>(
+    srcs: &[P],
+    options: ReadOptions,
+) -> Result<Vec<fastq::io::Reader<Box<dyn BufRead>>>, FilterError>
 where
     P: AsRef<Path>,
 {
     srcs.iter()
         .map(|src| {
             let src = src.as_ref();
-            fastq::fs::open(src).map_err(|e| FilterError::OpenFile(e, src.into()))
+            fastq::fs::open_with_options(src, options)
+                .map_err(|e| FilterError::OpenFile(e, src.into()))
         })
         .collect()
 }
 
-fn build_writers<P>(dsts: &[P]) -> Result<Vec<fastq::io::Writer<Box<dyn Write>>>, FilterError>
+fn build_writers<P>(
+    dsts: &[P],
+    options: WriteOptions,
+) -> Result<Vec<fastq::io::Writer<Box<dyn Write>>>, FilterError>
 where
     P: AsRef<Path>,
 {
     dsts.iter()
         .map(|dst| {
             let dst = dst.as_ref();
-            fastq::fs::create(dst).map_err(|e| FilterError::CreateFile(e, dst.into()))
+            fastq::fs::create_with_options(dst, options)
+                .map_err(|e| FilterError::CreateFile(e, dst.into()))
         })
         .collect()
 }
 
+fn ensure_single_stdio(
+    srcs: &[PathBuf],
+    dsts: &[PathBuf],
+    rejected_dsts: &[PathBuf],
+) -> Result<(), FilterError> {
+    if fastq::fs::has_multiple_stdio(srcs) {
+        Err(FilterError::MultipleStdinSources)
+    } else if fastq::fs::has_multiple_stdio(dsts.iter().chain(rejected_dsts)) {
+        Err(FilterError::MultipleStdoutDestinations)
+    } else {
+        Ok(())
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum FilterError {
     #[error("I/O error")]
@@ -213,10 +370,24 @@ pub enum FilterError {
     CreateFile(#[source] io::Error, PathBuf),
     #[error("could not read read names")]
     ReadNames(#[source] io::Error),
+    #[error("invalid fraction: expected [0.0, 1.0], got {0}")]
+    InvalidFraction(f64),
+    #[error("cannot invert {0} filter: filter is not set")]
+    InvalidInversion(PredicateKind),
+    #[error("{0} requires at least one filter")]
+    MissingFilter(&'static str),
+    #[error("invalid rejected destination count: expected {expected}, got {actual}")]
+    InvalidRejectedDestinationCount { expected: usize, actual: usize },
+    #[error("stdin (`-`) can only be used as one source")]
+    MultipleStdinSources,
+    #[error("stdout (`-`) can only be used as one destination")]
+    MultipleStdoutDestinations,
 }
 
 #[cfg(test)]
 mod tests {
+    use regex::bytes::Regex;
+
     use super::*;
 
     static DATA: &[u8] = b"\
@@ -225,9 +396,19 @@ mod tests {
 @fqlib:3/1\nGCCA\n+\ngcca
 ";
 
+    fn build_names_filter() -> Box<dyn Predicate> {
+        let names = [b"fqlib:2".to_vec()].into_iter().collect();
+        Box::new(NamesFilter::new(NameSet::Memory(names)))
+    }
+
+    fn build_sequence_pattern_filter(pattern: &str) -> Box<dyn Predicate> {
+        Box::new(SequencePatternFilter::new(Regex::new(pattern).unwrap()))
+    }
+
     #[test]
     fn test_copy_filtered() {
-        let names = [b"fqlib:2".to_vec()].iter().cloned().collect();
+        let mut expression = Expression::new(Combinator::All);
+        expression.push(build_names_filter(), false);
 
         let reader = fastq::io::Reader::new(DATA);
         let mut readers = [reader];
@@ -236,46 +417,223 @@ mod tests {
         let writer = fastq::io::Writer::new(&mut buf);
         let mut writers = [writer];
 
-        copy_filtered(&mut readers, &names, &mut writers).unwrap();
+        copy_filtered(&mut readers, &expression, &mut writers, &mut []).unwrap();
 
         let expected = b"@fqlib:2/1\nTCGA\n+\ndcba\n";
         assert_eq!(buf, expected);
     }
 
     #[test]
-    fn test_read_names() {
-        let data = "@fqlib:1/1\n@fqlib:2/1\n@fqlib:3/1\n";
+    fn test_copy_filtered_by_sequence_pattern() -> io::Result<()> {
+        let mut expression = Expression::new(Combinator::All);
+        expression.push(build_sequence_pattern_filter("^TC"), false);
 
-        let names = read_names(data.as_bytes()).unwrap();
+        let reader = fastq::io::Reader::new(DATA);
+        let mut readers = [reader];
 
-        assert_eq!(names.len(), 3);
-        assert!(names.contains("@fqlib:1/1".as_bytes()));
-        assert!(names.contains("@fqlib:2/1".as_bytes()));
-        assert!(names.contains("@fqlib:3/1".as_bytes()));
+        let writer = fastq::io::Writer::new(Vec::new());
+        let mut writers = [writer];
+
+        copy_filtered(&mut readers, &expression, &mut writers, &mut [])?;
+
+        let expected = b"@fqlib:2/1\nTCGA\n+\ndcba\n";
+        assert_eq!(writers[0].get_ref(), expected);
+
+        Ok(())
     }
 
     #[test]
-    fn test_name_id() {
-        assert_eq!(name_id("@fqlib:1/1".as_bytes()), b"fqlib:1");
-        assert_eq!(name_id("@fqlib:1 1".as_bytes()), b"fqlib:1");
-        assert_eq!(name_id("@fqlib:1".as_bytes()), b"fqlib:1");
+    fn test_copy_filtered_by_name_pattern() -> io::Result<()> {
+        let mut expression = Expression::new(Combinator::All);
+        expression.push(
+            Box::new(NamePatternFilter::new(Regex::new(":[13]/").unwrap())),
+            false,
+        );
+
+        let mut readers = [fastq::io::Reader::new(DATA)];
+        let mut writers = [fastq::io::Writer::new(Vec::new())];
+
+        copy_filtered(&mut readers, &expression, &mut writers, &mut [])?;
+
+        let expected = b"@fqlib:1/1\nAGCT\n+\nabcd\n@fqlib:3/1\nGCCA\n+\ngcca\n";
+        assert_eq!(writers[0].get_ref(), expected);
+
+        Ok(())
     }
 
     #[test]
-    fn test_copy_filtered_by_sequence_pattern() -> io::Result<()> {
-        let reader = fastq::io::Reader::new(DATA);
-        let mut readers = [reader];
+    fn test_copy_filtered_by_quality() -> io::Result<()> {
+        static R1: &[u8] = b"\
+@fqlib:1/1\nAGCT\n+\nIIII
+@fqlib:2/1\nTCGA\n+\n++++
+";
+
+        static R2: &[u8] = b"\
+@fqlib:1/2\nTCGA\n+\n++++
+@fqlib:2/2\nAGCT\n+\nIIII
+";
 
-        let pattern = Regex::new("^TC").unwrap();
+        let mut expression = Expression::new(Combinator::All);
+        expression.push(Box::new(QualityFilter::new(Some(30.0), None, None)), false);
 
-        let writer = fastq::io::Writer::new(Vec::new());
-        let mut writers = [writer];
+        let mut readers = [fastq::io::Reader::new(R1), fastq::io::Reader::new(R2)];
+        let mut writers = [
+            fastq::io::Writer::new(Vec::new()),
+            fastq::io::Writer::new(Vec::new()),
+        ];
 
-        copy_filtered_by_sequence_pattern(&mut readers, &pattern, &mut writers)?;
+        copy_filtered(&mut readers, &expression, &mut writers, &mut [])?;
 
-        let expected = b"@fqlib:2/1\nTCGA\n+\ndcba\n";
-        assert_eq!(writers[0].get_ref(), expected);
+        assert_eq!(writers[0].get_ref(), b"@fqlib:1/1\nAGCT\n+\nIIII\n");
+        assert_eq!(writers[1].get_ref(), b"@fqlib:1/2\nTCGA\n+\n++++\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_filtered_by_length() -> io::Result<()> {
+        static R1: &[u8] = b"\
+@fqlib:1/1\nAGCT\n+\nabcd
+@fqlib:2/1\nTCGA\n+\ndcba
+";
+
+        static R2: &[u8] = b"\
+@fqlib:1/2\nAG\n+\nab
+@fqlib:2/2\nTCGA\n+\ndcba
+";
+
+        let mut expression = Expression::new(Combinator::All);
+        let length_filter = LengthFilter::new(Some(4), None, PairPolicy::All);
+        expression.push(Box::new(length_filter), false);
+
+        let mut readers = [fastq::io::Reader::new(R1), fastq::io::Reader::new(R2)];
+        let mut writers = [
+            fastq::io::Writer::new(Vec::new()),
+            fastq::io::Writer::new(Vec::new()),
+        ];
+
+        copy_filtered(&mut readers, &expression, &mut writers, &mut [])?;
+
+        assert_eq!(writers[0].get_ref(), b"@fqlib:2/1\nTCGA\n+\ndcba\n");
+        assert_eq!(writers[1].get_ref(), b"@fqlib:2/2\nTCGA\n+\ndcba\n");
 
         Ok(())
     }
+
+    #[test]
+    fn test_copy_filtered_with_combined_predicates() -> io::Result<()> {
+        fn t(expression: &Expression, expected: &[u8]) -> io::Result<()> {
+            let mut readers = [fastq::io::Reader::new(DATA)];
+            let mut writers = [fastq::io::Writer::new(Vec::new())];
+            copy_filtered(&mut readers, expression, &mut writers, &mut [])?;
+            assert_eq!(writers[0].get_ref(), expected);
+            Ok(())
+        }
+
+        let mut expression = Expression::new(Combinator::All);
+        expression.push(build_names_filter(), false);
+        expression.push(build_sequence_pattern_filter("^AG"), false);
+        t(&expression, b"")?;
+
+        let mut expression = Expression::new(Combinator::Any);
+        expression.push(build_names_filter(), false);
+        expression.push(build_sequence_pattern_filter("^AG"), false);
+        t(
+            &expression,
+            b"@fqlib:1/1\nAGCT\n+\nabcd\n@fqlib:2/1\nTCGA\n+\ndcba\n",
+        )?;
+
+        let mut expression = Expression::new(Combinator::All);
+        expression.push(build_names_filter(), true);
+        expression.push(build_sequence_pattern_filter("^[AG]"), false);
+        t(
+            &expression,
+            b"@fqlib:1/1\nAGCT\n+\nabcd\n@fqlib:3/1\nGCCA\n+\ngcca\n",
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_filtered_with_rejected_writers() -> io::Result<()> {
+        static R2: &[u8] = b"\
+@fqlib:1/2\nTCGA\n+\ndcba
+@fqlib:2/2\nAGCT\n+\nabcd
+@fqlib:3/2\nTGGC\n+\nacgg
+";
+
+        let mut expression = Expression::new(Combinator::All);
+        expression.push(build_names_filter(), false);
+
+        let mut readers = [fastq::io::Reader::new(DATA), fastq::io::Reader::new(R2)];
+        let mut writers = [
+            fastq::io::Writer::new(Vec::new()),
+            fastq::io::Writer::new(Vec::new()),
+        ];
+        let mut rejected_writers = [
+            fastq::io::Writer::new(Vec::new()),
+            fastq::io::Writer::new(Vec::new()),
+        ];
+
+        let (n, total) = copy_filtered(
+            &mut readers,
+            &expression,
+            &mut writers,
+            &mut rejected_writers,
+        )?;
+
+        assert_eq!((n, total), (1, 3));
+
+        assert_eq!(writers[0].get_ref(), b"@fqlib:2/1\nTCGA\n+\ndcba\n");
+        assert_eq!(writers[1].get_ref(), b"@fqlib:2/2\nAGCT\n+\nabcd\n");
+
+        assert_eq!(
+            rejected_writers[0].get_ref(),
+            b"@fqlib:1/1\nAGCT\n+\nabcd\n@fqlib:3/1\nGCCA\n+\ngcca\n"
+        );
+        assert_eq!(
+            rejected_writers[1].get_ref(),
+            b"@fqlib:1/2\nTCGA\n+\ndcba\n@fqlib:3/2\nTGGC\n+\nacgg\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_filtered_with_unexpected_eof() {
+        let mut readers = [
+            fastq::io::Reader::new(DATA),
+            fastq::io::Reader::new(&DATA[..23]),
+        ];
+        let mut writers = [
+            fastq::io::Writer::new(Vec::new()),
+            fastq::io::Writer::new(Vec::new()),
+        ];
+
+        assert!(matches!(
+            _filter(&mut readers, &mut writers, &mut [], |_| Ok(true)),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+
+    #[test]
+    fn test_ensure_single_stdio() {
+        let paths = |paths: &[&str]| -> Vec<PathBuf> { paths.iter().map(PathBuf::from).collect() };
+
+        assert!(ensure_single_stdio(&paths(&["-"]), &paths(&["-"]), &[]).is_ok());
+        assert!(ensure_single_stdio(&paths(&["-", "r2.fq"]), &paths(&["a.fq", "-"]), &[]).is_ok());
+
+        assert!(matches!(
+            ensure_single_stdio(&paths(&["-", "-"]), &paths(&["a.fq", "b.fq"]), &[]),
+            Err(FilterError::MultipleStdinSources)
+        ));
+        assert!(matches!(
+            ensure_single_stdio(&paths(&["r1.fq"]), &paths(&["-", "-"]), &[]),
+            Err(FilterError::MultipleStdoutDestinations)
+        ));
+        assert!(matches!(
+            ensure_single_stdio(&paths(&["r1.fq"]), &paths(&["-"]), &paths(&["-"])),
+            Err(FilterError::MultipleStdoutDestinations)
+        ));
+    }
 }
@@ -0,0 +1,92 @@
+use std::io;
+
+use super::{Predicate, PredicateKind};
+use crate::fastq::Record;
+
+/// How the results of a predicate are combined for a set of records, e.g., mates in a pair.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum PairPolicy {
+    /// Keep the set only if all records pass, i.e., drop the set if any record fails.
+    #[default]
+    All,
+    /// Keep the set if any record passes, i.e., drop the set only if all records fail.
+    Any,
+}
+
+impl PairPolicy {
+    /// Applies the predicate `f` to each record and combines the results.
+    pub fn apply<F>(self, records: &[Record], f: F) -> bool
+    where
+        F: Fn(&Record) -> bool,
+    {
+        match self {
+            Self::All => records.iter().all(f),
+            Self::Any => records.iter().any(f),
+        }
+    }
+}
+
+/// A record predicate using the sequence length of a record.
+#[derive(Debug)]
+pub struct LengthFilter {
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    pair_policy: PairPolicy,
+}
+
+impl LengthFilter {
+    /// Creates a length filter with inclusive bounds.
+    pub fn new(
+        min_length: Option<usize>,
+        max_length: Option<usize>,
+        pair_policy: PairPolicy,
+    ) -> Self {
+        Self {
+            min_length,
+            max_length,
+            pair_policy,
+        }
+    }
+}
+
+impl Predicate for LengthFilter {
+    fn kind(&self) -> PredicateKind {
+        PredicateKind::Length
+    }
+
+    /// Returns whether the set of records passes the length bounds using the pair policy.
+    fn is_match(&self, records: &[Record]) -> io::Result<bool> {
+        Ok(self.pair_policy.apply(records, |record| {
+            let len = record.sequence().len();
+
+            self.min_length.is_none_or(|min| len >= min)
+                && self.max_length.is_none_or(|max| len <= max)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_match() -> io::Result<()> {
+        let records = [
+            Record::new("@r0/1", "ACGTACGT", "+", "IIIIIIII"),
+            Record::new("@r0/2", "ACG", "+", "III"),
+        ];
+
+        let filter = LengthFilter::new(Some(3), Some(8), PairPolicy::All);
+        assert!(filter.is_match(&records)?);
+
+        let filter = LengthFilter::new(Some(4), None, PairPolicy::All);
+        assert!(!filter.is_match(&records)?);
+        let filter = LengthFilter::new(Some(4), None, PairPolicy::Any);
+        assert!(filter.is_match(&records)?);
+
+        let filter = LengthFilter::new(None, Some(2), PairPolicy::Any);
+        assert!(!filter.is_match(&records)?);
+
+        Ok(())
+    }
+}
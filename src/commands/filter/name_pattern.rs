@@ -0,0 +1,53 @@
+use std::io;
+
+use regex::bytes::Regex;
+
+use super::{Predicate, PredicateKind};
+use crate::fastq::Record;
+
+/// A predicate that tests whether the record definition matches a regular expression.
+///
+/// The definition is the full header line, i.e., the `@` prefix, name, and description.
+pub struct NamePatternFilter {
+    pattern: Regex,
+}
+
+impl NamePatternFilter {
+    pub fn new(pattern: Regex) -> Self {
+        Self { pattern }
+    }
+}
+
+impl Predicate for NamePatternFilter {
+    fn kind(&self) -> PredicateKind {
+        PredicateKind::NamePattern
+    }
+
+    fn is_match(&self, records: &[Record]) -> io::Result<bool> {
+        Ok(self.pattern.is_match(records[0].definition()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_match() -> io::Result<()> {
+        let filter = NamePatternFilter::new(Regex::new(":1101:").unwrap());
+
+        let records = [Record::new("@fqlib:1:1101:1/1", "TCGA", "+", "dcba")];
+        assert!(filter.is_match(&records)?);
+
+        let records = [Record::new("@fqlib:1:1102:1/1", "TCGA", "+", "dcba")];
+        assert!(!filter.is_match(&records)?);
+
+        let filter = NamePatternFilter::new(Regex::new(r" 1:N:0:ACGT$").unwrap());
+
+        let mut record = Record::new("@fqlib:1 1:N:0:ACGT", "TCGA", "+", "dcba");
+        record.reset(None);
+        assert!(filter.is_match(&[record])?);
+
+        Ok(())
+    }
+}
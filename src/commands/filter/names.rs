@@ -0,0 +1,201 @@
+mod sorted;
+
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, BufRead, Read, Seek},
+};
+
+pub use self::sorted::SortedNames;
+
+use super::{Predicate, PredicateKind};
+use crate::{collections::ScalableBloomFilter, fastq::Record};
+
+/// How a list of names is stored and searched.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum NamesIndex {
+    /// Load all names into memory.
+    #[default]
+    Memory,
+    /// Search a bytewise sorted names file on disk using a sparse index in memory.
+    Sorted,
+    /// Check a Bloom filter in memory before searching a bytewise sorted names file on disk.
+    Bloom,
+}
+
+/// A set of names.
+pub enum NameSet<R = File> {
+    Memory(HashSet<Vec<u8>>),
+    Sorted(SortedNames<R>),
+    Bloom(ScalableBloomFilter, SortedNames<R>),
+}
+
+impl<R> NameSet<R>
+where
+    R: Read + Seek,
+{
+    /// Returns the number of names in the set.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Memory(names) => names.len(),
+            Self::Sorted(names) | Self::Bloom(_, names) => names.len(),
+        }
+    }
+
+    /// Returns whether the set contains the given name.
+    ///
+    /// When using a Bloom filter, names that may be in the set are verified on disk, so the result
+    /// is always exact.
+    pub fn contains(&self, name: &[u8]) -> io::Result<bool> {
+        match self {
+            Self::Memory(names) => Ok(names.contains(name)),
+            Self::Sorted(names) => names.contains(name),
+            Self::Bloom(filter, names) => {
+                if filter.contains(name) {
+                    names.contains(name)
+                } else {
+                    Ok(false)
+                }
+            }
+        }
+    }
+}
+
+/// A predicate that tests whether the record name is in (allowlist) or not in (denylist) a set of
+/// names.
+pub struct NamesFilter {
+    names: NameSet,
+    is_denylist: bool,
+}
+
+impl NamesFilter {
+    /// Creates a filter that matches records with names in the given set.
+    pub fn new(names: NameSet) -> Self {
+        Self {
+            names,
+            is_denylist: false,
+        }
+    }
+
+    /// Creates a filter that matches records with names not in the given set.
+    pub fn excluding(names: NameSet) -> Self {
+        Self {
+            names,
+            is_denylist: true,
+        }
+    }
+}
+
+impl Predicate for NamesFilter {
+    fn kind(&self) -> PredicateKind {
+        if self.is_denylist {
+            PredicateKind::ExcludeNames
+        } else {
+            PredicateKind::Names
+        }
+    }
+
+    fn is_match(&self, records: &[Record]) -> io::Result<bool> {
+        let id = name_id(records[0].name());
+        self.names
+            .contains(id)
+            .map(|is_match| is_match != self.is_denylist)
+    }
+}
+
+pub fn read_names<R>(reader: R) -> io::Result<HashSet<Vec<u8>>>
+where
+    R: BufRead,
+{
+    reader
+        .lines()
+        .map(|res| res.map(|line| line.into_bytes()))
+        .collect()
+}
+
+// Names always begin with an `@` character.
+const ID_START_OFFSET: usize = 1;
+
+fn name_id(name: &[u8]) -> &[u8] {
+    let pos = name.iter().rev().position(|&b| b == b'/' || b == b' ');
+
+    if let Some(i) = pos {
+        let len = name.len();
+        let end = len - i - 1;
+        &name[ID_START_OFFSET..end]
+    } else {
+        &name[ID_START_OFFSET..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_match() -> io::Result<()> {
+        let names = [b"fqlib:2".to_vec()].into_iter().collect();
+        let filter = NamesFilter::new(NameSet::Memory(names));
+
+        let records = [Record::new("@fqlib:2/1", "", "", "")];
+        assert!(filter.is_match(&records)?);
+
+        let records = [Record::new("@fqlib:1/1", "", "", "")];
+        assert!(!filter.is_match(&records)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_match_with_denylist() -> io::Result<()> {
+        let names = [b"fqlib:2".to_vec()].into_iter().collect();
+        let filter = NamesFilter::excluding(NameSet::Memory(names));
+
+        let records = [Record::new("@fqlib:2/1", "", "", "")];
+        assert!(!filter.is_match(&records)?);
+
+        let records = [Record::new("@fqlib:1/1", "", "", "")];
+        assert!(filter.is_match(&records)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_name_set_contains() -> io::Result<()> {
+        use std::io::Cursor;
+
+        let data = b"fqlib:1\nfqlib:2\n";
+
+        let mut filter = ScalableBloomFilter::new(0.0001, 8);
+        let names = SortedNames::read(Cursor::new(data), |name| {
+            filter.insert(name);
+        })?;
+        let names = NameSet::Bloom(filter, names);
+
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(b"fqlib:1")?);
+        assert!(names.contains(b"fqlib:2")?);
+        assert!(!names.contains(b"fqlib:3")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_names() {
+        let data = "@fqlib:1/1\n@fqlib:2/1\n@fqlib:3/1\n";
+
+        let names = read_names(data.as_bytes()).unwrap();
+
+        assert_eq!(names.len(), 3);
+        assert!(names.contains("@fqlib:1/1".as_bytes()));
+        assert!(names.contains("@fqlib:2/1".as_bytes()));
+        assert!(names.contains("@fqlib:3/1".as_bytes()));
+    }
+
+    #[test]
+    fn test_name_id() {
+        assert_eq!(name_id("@fqlib:1/1".as_bytes()), b"fqlib:1");
+        assert_eq!(name_id("@fqlib:1 1".as_bytes()), b"fqlib:1");
+        assert_eq!(name_id("@fqlib:1".as_bytes()), b"fqlib:1");
+    }
+}
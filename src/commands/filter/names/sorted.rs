@@ -0,0 +1,243 @@
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    mem,
+};
+
+// The number of names in each block of the sparse index.
+const BLOCK_SIZE: usize = 1024;
+
+/// A list of names in a sorted file that is searched on disk.
+///
+/// The file must have one name per line, sorted bytewise, e.g., using `LC_ALL=C sort`. Every
+/// 1024th name and its offset are held in memory as a sparse index. A lookup binary searches the
+/// index and then reads and scans a single block of names from disk.
+pub struct SortedNames<R = File> {
+    blocks: Vec<Block>,
+    end: u64,
+    len: usize,
+    state: RefCell<State<R>>,
+}
+
+struct Block {
+    first_name: Vec<u8>,
+    start: u64,
+}
+
+struct State<R> {
+    inner: R,
+    buf: Vec<u8>,
+    block_index: Option<usize>,
+}
+
+impl<R> SortedNames<R>
+where
+    R: Read + Seek,
+{
+    /// Reads a list of sorted names.
+    ///
+    /// This reads the entire input once to check that it is sorted and to build the sparse index.
+    /// Each name is passed to `visit`, in order.
+    pub fn read<F>(inner: R, mut visit: F) -> io::Result<Self>
+    where
+        F: FnMut(&[u8]),
+    {
+        let mut reader = BufReader::new(inner);
+
+        let mut blocks = Vec::new();
+        let mut prev_name = Vec::new();
+        let mut name = Vec::new();
+        let mut end = 0;
+        let mut len = 0;
+
+        loop {
+            name.clear();
+
+            let start = end;
+
+            match read_name(&mut reader, &mut name)? {
+                0 => break,
+                n => end += n as u64,
+            }
+
+            if len > 0 && name < prev_name {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "names are not sorted: '{}' is after '{}'",
+                        String::from_utf8_lossy(&name),
+                        String::from_utf8_lossy(&prev_name)
+                    ),
+                ));
+            }
+
+            if len % BLOCK_SIZE == 0 {
+                blocks.push(Block {
+                    first_name: name.clone(),
+                    start,
+                });
+            }
+
+            visit(&name);
+            len += 1;
+
+            mem::swap(&mut prev_name, &mut name);
+        }
+
+        Ok(Self {
+            blocks,
+            end,
+            len,
+            state: RefCell::new(State {
+                inner: reader.into_inner(),
+                buf: Vec::new(),
+                block_index: None,
+            }),
+        })
+    }
+
+    /// Returns the number of names in the list.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the list contains the given name.
+    ///
+    /// This finds the only block that can contain the name using the sparse index and scans it.
+    /// The last block read is kept in memory, so consecutive lookups in the same block do not
+    /// read from disk.
+    pub fn contains(&self, name: &[u8]) -> io::Result<bool> {
+        let i = self
+            .blocks
+            .partition_point(|block| block.first_name.as_slice() <= name);
+
+        let Some(block_index) = i.checked_sub(1) else {
+            return Ok(false);
+        };
+
+        let mut state = self.state.borrow_mut();
+
+        if state.block_index != Some(block_index) {
+            let start = self.blocks[block_index].start;
+            let end = self.blocks.get(i).map_or(self.end, |block| block.start);
+
+            // Invalidate the cache first in case reading fails.
+            state.block_index = None;
+
+            let State { inner, buf, .. } = &mut *state;
+            inner.seek(SeekFrom::Start(start))?;
+            buf.resize((end - start) as usize, 0);
+            inner.read_exact(buf)?;
+
+            state.block_index = Some(block_index);
+        }
+
+        let buf = state.buf.strip_suffix(b"\n").unwrap_or(&state.buf);
+
+        for line in buf.split(|&b| b == b'\n') {
+            let block_name = line.strip_suffix(b"\r").unwrap_or(line);
+
+            match block_name.cmp(name) {
+                Ordering::Less => {}
+                Ordering::Equal => return Ok(true),
+                Ordering::Greater => break,
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+// Reads a line without its line ending. This returns the number of bytes read, including the line
+// ending.
+fn read_name<R>(reader: &mut R, buf: &mut Vec<u8>) -> io::Result<usize>
+where
+    R: BufRead,
+{
+    let n = reader.read_until(b'\n', buf)?;
+
+    if buf.ends_with(b"\n") {
+        buf.pop();
+
+        if buf.ends_with(b"\r") {
+            buf.pop();
+        }
+    }
+
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_read() -> io::Result<()> {
+        let data = b"fqlib:1\nfqlib:10\nfqlib:2\n";
+
+        let mut names = Vec::new();
+        let sorted_names = SortedNames::read(Cursor::new(data), |name| names.push(name.to_vec()))?;
+
+        assert_eq!(sorted_names.len(), 3);
+        assert_eq!(
+            names,
+            [
+                b"fqlib:1".to_vec(),
+                b"fqlib:10".to_vec(),
+                b"fqlib:2".to_vec()
+            ]
+        );
+
+        let data = b"fqlib:2\nfqlib:1\n";
+        assert!(matches!(
+            SortedNames::read(Cursor::new(data), |_| {}),
+            Err(e) if e.kind() == io::ErrorKind::InvalidData
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_contains() -> io::Result<()> {
+        let data = b"a\nab\nb\nbcd\nc\nc\ncdef\nd\r\ne";
+        let names = SortedNames::read(Cursor::new(data), |_| {})?;
+
+        for name in ["a", "ab", "b", "bcd", "c", "cdef", "d", "e"] {
+            assert!(names.contains(name.as_bytes())?, "{name}");
+        }
+
+        for name in ["", "aa", "abc", "bc", "cd", "dd", "f"] {
+            assert!(!names.contains(name.as_bytes())?, "{name}");
+        }
+
+        let data: Vec<u8> = (0..BLOCK_SIZE * 2 + 1)
+            .flat_map(|i| format!("fqlib:{i:05}\n").into_bytes())
+            .collect();
+        let names = SortedNames::read(Cursor::new(data), |_| {})?;
+
+        for i in [
+            0,
+            BLOCK_SIZE - 1,
+            BLOCK_SIZE,
+            BLOCK_SIZE + 1,
+            BLOCK_SIZE * 2,
+            7,
+        ] {
+            assert!(names.contains(format!("fqlib:{i:05}").as_bytes())?, "{i}");
+        }
+
+        for name in ["fqlib", "fqlib:0", "fqlib:00000a", "fqlib:99999"] {
+            assert!(!names.contains(name.as_bytes())?, "{name}");
+        }
+
+        let names = SortedNames::read(Cursor::new(b""), |_| {})?;
+        assert_eq!(names.len(), 0);
+        assert!(!names.contains(b"a")?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+use std::{fmt, io};
+
+use crate::fastq::Record;
+
+/// A filter kind, as named on the command line.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum PredicateKind {
+    /// `--names`
+    Names,
+    /// `--exclude-names`
+    ExcludeNames,
+    /// `--name-pattern`
+    NamePattern,
+    /// `--sequence-pattern`
+    SequencePattern,
+    /// `--sequence-motif`
+    SequenceMotif,
+    /// `--min-mean-quality`, `--max-expected-errors`, and `--min-base-quality-fraction`
+    Quality,
+    /// `--min-length` and `--max-length`
+    Length,
+}
+
+impl fmt::Display for PredicateKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Names => f.write_str("names"),
+            Self::ExcludeNames => f.write_str("exclude-names"),
+            Self::NamePattern => f.write_str("name-pattern"),
+            Self::SequencePattern => f.write_str("sequence-pattern"),
+            Self::SequenceMotif => f.write_str("sequence-motif"),
+            Self::Quality => f.write_str("quality"),
+            Self::Length => f.write_str("length"),
+        }
+    }
+}
+
+/// A test of a set of records, e.g., mates in a pair.
+///
+/// Predicates that only consider a single record use the record from the first source.
+pub trait Predicate {
+    fn kind(&self) -> PredicateKind;
+    fn is_match(&self, records: &[Record]) -> io::Result<bool>;
+}
+
+/// How the results of multiple predicates are combined.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Combinator {
+    /// All predicates must match.
+    #[default]
+    All,
+    /// At least one predicate must match.
+    Any,
+}
+
+/// A list of predicates, each optionally negated, combined into a single predicate.
+pub struct Expression {
+    terms: Vec<(Box<dyn Predicate>, bool)>,
+    combinator: Combinator,
+    is_inverted: bool,
+}
+
+impl Expression {
+    /// Creates an empty expression using the given combinator.
+    pub fn new(combinator: Combinator) -> Self {
+        Self {
+            terms: Vec::new(),
+            combinator,
+            is_inverted: false,
+        }
+    }
+
+    /// Sets whether the result of the whole expression is negated.
+    pub fn set_inverted(&mut self, is_inverted: bool) {
+        self.is_inverted = is_inverted;
+    }
+
+    /// Adds a predicate to the expression, negating its result if `is_inverted` is set.
+    pub fn push(&mut self, predicate: Box<dyn Predicate>, is_inverted: bool) {
+        self.terms.push((predicate, is_inverted));
+    }
+
+    /// Returns whether the expression has no predicates.
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Evaluates the expression for the given set of records.
+    ///
+    /// Evaluation short-circuits, i.e., stops at the first predicate that determines the result.
+    pub fn is_match(&self, records: &[Record]) -> io::Result<bool> {
+        self.evaluate(records)
+            .map(|is_match| is_match != self.is_inverted)
+    }
+
+    fn evaluate(&self, records: &[Record]) -> io::Result<bool> {
+        for (predicate, is_inverted) in &self.terms {
+            let is_match = predicate.is_match(records)? != *is_inverted;
+
+            match (self.combinator, is_match) {
+                (Combinator::All, false) => return Ok(false),
+                (Combinator::Any, true) => return Ok(true),
+                _ => {}
+            }
+        }
+
+        Ok(self.combinator == Combinator::All)
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let separator = match self.combinator {
+            Combinator::All => " AND ",
+            Combinator::Any => " OR ",
+        };
+
+        if self.is_inverted {
+            f.write_str("NOT (")?;
+        }
+
+        for (i, (predicate, is_inverted)) in self.terms.iter().enumerate() {
+            if i > 0 {
+                f.write_str(separator)?;
+            }
+
+            if *is_inverted {
+                f.write_str("NOT ")?;
+            }
+
+            write!(f, "{}", predicate.kind())?;
+        }
+
+        if self.is_inverted {
+            f.write_str(")")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Constant(PredicateKind, bool);
+
+    impl Predicate for Constant {
+        fn kind(&self) -> PredicateKind {
+            self.0
+        }
+
+        fn is_match(&self, _: &[Record]) -> io::Result<bool> {
+            Ok(self.1)
+        }
+    }
+
+    fn build_expression(combinator: Combinator, terms: &[(bool, bool)]) -> Expression {
+        let mut expression = Expression::new(combinator);
+
+        for &(value, is_inverted) in terms {
+            expression.push(Box::new(Constant(PredicateKind::Names, value)), is_inverted);
+        }
+
+        expression
+    }
+
+    #[test]
+    fn test_is_match() -> io::Result<()> {
+        let records = [Record::default()];
+
+        let expression = build_expression(Combinator::All, &[(true, false), (true, false)]);
+        assert!(expression.is_match(&records)?);
+        let expression = build_expression(Combinator::All, &[(true, false), (false, false)]);
+        assert!(!expression.is_match(&records)?);
+        let expression = build_expression(Combinator::All, &[(true, false), (false, true)]);
+        assert!(expression.is_match(&records)?);
+
+        let expression = build_expression(Combinator::Any, &[(false, false), (true, false)]);
+        assert!(expression.is_match(&records)?);
+        let expression = build_expression(Combinator::Any, &[(false, false), (false, false)]);
+        assert!(!expression.is_match(&records)?);
+        let expression = build_expression(Combinator::Any, &[(false, false), (true, true)]);
+        assert!(!expression.is_match(&records)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_match_with_inverted_expression() -> io::Result<()> {
+        let records = [Record::default()];
+
+        let mut expression = build_expression(Combinator::All, &[(true, false), (false, false)]);
+        expression.set_inverted(true);
+        assert!(expression.is_match(&records)?);
+
+        let mut expression = build_expression(Combinator::Any, &[(true, false), (false, false)]);
+        expression.set_inverted(true);
+        assert!(!expression.is_match(&records)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fmt() {
+        let mut expression = build_expression(Combinator::Any, &[(true, false), (true, true)]);
+        assert_eq!(expression.to_string(), "names OR NOT names");
+
+        expression.set_inverted(true);
+        assert_eq!(expression.to_string(), "NOT (names OR NOT names)");
+    }
+}
@@ -0,0 +1,131 @@
+use std::io;
+
+use super::{Predicate, PredicateKind};
+use crate::{
+    fastq::Record,
+    metrics::{decode_score, error_probability_to_phred_score, phred_score_to_error_probability},
+};
+
+/// A record predicate using the quality scores of a record.
+///
+/// All set thresholds must pass for a record to match. As a [`Predicate`], this tests the record
+/// from the first source.
+#[derive(Debug)]
+pub struct QualityFilter {
+    min_mean_quality: Option<f64>,
+    max_expected_errors: Option<f64>,
+    min_base_quality_fraction: Option<(u8, f64)>,
+}
+
+impl QualityFilter {
+    /// Creates a quality filter.
+    ///
+    /// `min_base_quality_fraction` is a tuple of a base quality score threshold and the minimum
+    /// fraction of bases that must have a quality score at or above it.
+    pub fn new(
+        min_mean_quality: Option<f64>,
+        max_expected_errors: Option<f64>,
+        min_base_quality_fraction: Option<(u8, f64)>,
+    ) -> Self {
+        Self {
+            min_mean_quality,
+            max_expected_errors,
+            min_base_quality_fraction,
+        }
+    }
+
+    /// Returns whether the quality scores of the given record pass all set thresholds.
+    pub fn is_record_match(&self, record: &Record) -> io::Result<bool> {
+        let quality_scores = record.quality_scores();
+        let threshold = self.min_base_quality_fraction.map(|(q, _)| q);
+
+        let mut expected_errors = 0.0;
+        let mut passing_base_count = 0;
+
+        for &c in quality_scores {
+            let q = decode_score(c)?;
+            expected_errors += phred_score_to_error_probability(q);
+
+            if threshold.is_some_and(|t| q >= t) {
+                passing_base_count += 1;
+            }
+        }
+
+        let len = quality_scores.len() as f64;
+
+        // The mean quality score is calculated from the mean error probability, as in the
+        // `avg_quality_score_per_position` metric.
+        if let Some(min_mean_quality) = self.min_mean_quality
+            && (quality_scores.is_empty()
+                || error_probability_to_phred_score(expected_errors / len) < min_mean_quality)
+        {
+            return Ok(false);
+        }
+
+        if let Some(max_expected_errors) = self.max_expected_errors
+            && expected_errors > max_expected_errors
+        {
+            return Ok(false);
+        }
+
+        if let Some((_, min_fraction)) = self.min_base_quality_fraction
+            && (quality_scores.is_empty() || (passing_base_count as f64) / len < min_fraction)
+        {
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+}
+
+impl Predicate for QualityFilter {
+    fn kind(&self) -> PredicateKind {
+        PredicateKind::Quality
+    }
+
+    fn is_match(&self, records: &[Record]) -> io::Result<bool> {
+        self.is_record_match(&records[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_record_match() -> io::Result<()> {
+        // Q40, Q30, Q20, Q10
+        let record = Record::new("@r0", "ACGT", "+", "I?5+");
+
+        let filter = QualityFilter::new(Some(15.0), None, None);
+        assert!(filter.is_record_match(&record)?);
+        let filter = QualityFilter::new(Some(20.0), None, None);
+        assert!(!filter.is_record_match(&record)?);
+
+        // 0.0001 + 0.001 + 0.01 + 0.1 = 0.1111
+        let filter = QualityFilter::new(None, Some(0.2), None);
+        assert!(filter.is_record_match(&record)?);
+        let filter = QualityFilter::new(None, Some(0.1), None);
+        assert!(!filter.is_record_match(&record)?);
+
+        let filter = QualityFilter::new(None, None, Some((30, 0.5)));
+        assert!(filter.is_record_match(&record)?);
+        let filter = QualityFilter::new(None, None, Some((30, 0.75)));
+        assert!(!filter.is_record_match(&record)?);
+
+        let filter = QualityFilter::new(Some(15.0), Some(0.2), Some((30, 0.75)));
+        assert!(!filter.is_record_match(&record)?);
+
+        let record = Record::new("@r0", "", "+", "");
+        assert!(!QualityFilter::new(Some(0.0), None, None).is_record_match(&record)?);
+        assert!(QualityFilter::new(None, Some(0.0), None).is_record_match(&record)?);
+
+        let record = Record::new("@r0", "A", "+", "\x00");
+        assert!(matches!(
+            QualityFilter::new(None, Some(1.0), None).is_record_match(&record),
+            Err(e) if e.kind() == io::ErrorKind::InvalidData
+        ));
+
+        Ok(())
+    }
+}
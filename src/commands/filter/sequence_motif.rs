@@ -0,0 +1,259 @@
+use std::{io, str::FromStr};
+
+use super::{Predicate, PredicateKind};
+use crate::fastq::Record;
+
+// Bases are encoded as bit sets so that an IUPAC code matches a base if they share a bit.
+const A: u8 = 0b0001;
+const C: u8 = 0b0010;
+const G: u8 = 0b0100;
+const T: u8 = 0b1000;
+
+/// How mismatches between a motif and a sequence are counted.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum MotifDistance {
+    /// Count substitutions.
+    #[default]
+    Hamming,
+    /// Count substitutions, insertions, and deletions.
+    Levenshtein,
+}
+
+/// A sequence motif that may include IUPAC ambiguity codes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Motif(Vec<u8>);
+
+impl Motif {
+    /// Returns the reverse complement of the motif.
+    pub fn reverse_complement(&self) -> Self {
+        Self(self.0.iter().rev().map(|&code| complement(code)).collect())
+    }
+
+    /// Returns whether the sequence contains the motif with at most `k` mismatches.
+    pub fn is_match(&self, sequence: &[u8], distance: MotifDistance, k: usize) -> bool {
+        match distance {
+            MotifDistance::Hamming => self.is_hamming_match(sequence, k),
+            MotifDistance::Levenshtein => self.is_levenshtein_match(sequence, k),
+        }
+    }
+
+    fn is_hamming_match(&self, sequence: &[u8], k: usize) -> bool {
+        sequence.windows(self.0.len()).any(|window| {
+            let mut mismatches = 0;
+
+            for (&code, &b) in self.0.iter().zip(window) {
+                if code & encode_base(b) == 0 {
+                    mismatches += 1;
+
+                    if mismatches > k {
+                        return false;
+                    }
+                }
+            }
+
+            true
+        })
+    }
+
+    // This is the semi-global edit distance, i.e., the motif can start and end anywhere in the
+    // sequence. See Sellers, P. H. (1980). "The theory and computation of evolutionary distances:
+    // Pattern recognition". Journal of Algorithms, 1(4), 359–373.
+    fn is_levenshtein_match(&self, sequence: &[u8], k: usize) -> bool {
+        // the last column of the edit distance matrix, indexed by motif position
+        let mut distances: Vec<usize> = (0..=self.0.len()).collect();
+
+        if distances[self.0.len()] <= k {
+            return true;
+        }
+
+        for &b in sequence {
+            let base = encode_base(b);
+
+            let mut diagonal = distances[0];
+            distances[0] = 0;
+
+            for (i, &code) in self.0.iter().enumerate() {
+                let cost = usize::from(code & base == 0);
+                let distance = (diagonal + cost)
+                    .min(distances[i + 1] + 1)
+                    .min(distances[i] + 1);
+
+                diagonal = distances[i + 1];
+                distances[i + 1] = distance;
+            }
+
+            if distances[self.0.len()] <= k {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+impl FromStr for Motif {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err("empty motif");
+        }
+
+        s.bytes()
+            .map(|b| encode_code(b).ok_or("invalid IUPAC code"))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// A predicate that tests whether the record sequence contains a motif with at most a given
+/// number of mismatches.
+pub struct SequenceMotifFilter {
+    motifs: Vec<Motif>,
+    distance: MotifDistance,
+    max_mismatches: usize,
+}
+
+impl SequenceMotifFilter {
+    /// Creates a sequence motif filter.
+    ///
+    /// If `search_reverse_complement` is set, records also match if their sequence contains the
+    /// reverse complement of the motif.
+    pub fn new(
+        motif: Motif,
+        distance: MotifDistance,
+        max_mismatches: usize,
+        search_reverse_complement: bool,
+    ) -> Self {
+        let mut motifs = vec![motif];
+
+        if search_reverse_complement {
+            motifs.push(motifs[0].reverse_complement());
+        }
+
+        Self {
+            motifs,
+            distance,
+            max_mismatches,
+        }
+    }
+}
+
+impl Predicate for SequenceMotifFilter {
+    fn kind(&self) -> PredicateKind {
+        PredicateKind::SequenceMotif
+    }
+
+    fn is_match(&self, records: &[Record]) -> io::Result<bool> {
+        let sequence = records[0].sequence();
+
+        Ok(self
+            .motifs
+            .iter()
+            .any(|motif| motif.is_match(sequence, self.distance, self.max_mismatches)))
+    }
+}
+
+fn encode_code(b: u8) -> Option<u8> {
+    match b.to_ascii_uppercase() {
+        b'A' => Some(A),
+        b'C' => Some(C),
+        b'G' => Some(G),
+        b'T' | b'U' => Some(T),
+        b'R' => Some(A | G),
+        b'Y' => Some(C | T),
+        b'S' => Some(C | G),
+        b'W' => Some(A | T),
+        b'K' => Some(G | T),
+        b'M' => Some(A | C),
+        b'B' => Some(C | G | T),
+        b'D' => Some(A | G | T),
+        b'H' => Some(A | C | T),
+        b'V' => Some(A | C | G),
+        b'N' => Some(A | C | G | T),
+        _ => None,
+    }
+}
+
+// Ambiguous bases in a sequence, e.g., `N`, never match.
+fn encode_base(b: u8) -> u8 {
+    match b.to_ascii_uppercase() {
+        b'A' => A,
+        b'C' => C,
+        b'G' => G,
+        b'T' | b'U' => T,
+        _ => 0,
+    }
+}
+
+fn complement(code: u8) -> u8 {
+    ((code & A) << 3) | ((code & C) << 1) | ((code & G) >> 1) | ((code & T) >> 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("ACgu".parse(), Ok(Motif(vec![A, C, G, T])));
+        assert_eq!("RN".parse(), Ok(Motif(vec![A | G, A | C | G | T])));
+        assert_eq!("".parse::<Motif>(), Err("empty motif"));
+        assert_eq!("ACX".parse::<Motif>(), Err("invalid IUPAC code"));
+    }
+
+    #[test]
+    fn test_reverse_complement() -> Result<(), &'static str> {
+        let motif: Motif = "AACGR".parse()?;
+        assert_eq!(motif.reverse_complement(), "YCGTT".parse()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_match_with_hamming_distance() -> Result<(), &'static str> {
+        let motif: Motif = "ACGT".parse()?;
+
+        assert!(motif.is_match(b"TTACGTTT", MotifDistance::Hamming, 0));
+        assert!(!motif.is_match(b"TTAGGTTT", MotifDistance::Hamming, 0));
+        assert!(motif.is_match(b"TTAGGTTT", MotifDistance::Hamming, 1));
+        assert!(!motif.is_match(b"ACG", MotifDistance::Hamming, 1));
+        assert!(!motif.is_match(b"ANGT", MotifDistance::Hamming, 0));
+
+        let motif: Motif = "ACNT".parse()?;
+        assert!(motif.is_match(b"ACAT", MotifDistance::Hamming, 0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_match_with_levenshtein_distance() -> Result<(), &'static str> {
+        let motif: Motif = "ACGT".parse()?;
+
+        assert!(motif.is_match(b"TTACGTTT", MotifDistance::Levenshtein, 0));
+        // deletion
+        assert!(!motif.is_match(b"TTACTTT", MotifDistance::Levenshtein, 0));
+        assert!(motif.is_match(b"TTACTTT", MotifDistance::Levenshtein, 1));
+        // insertion
+        assert!(!motif.is_match(b"GGACTGTGG", MotifDistance::Levenshtein, 0));
+        assert!(motif.is_match(b"GGACTGTGG", MotifDistance::Levenshtein, 1));
+        assert!(!motif.is_match(b"GGACTGTGG", MotifDistance::Hamming, 1));
+
+        assert!(motif.is_match(b"AC", MotifDistance::Levenshtein, 2));
+        assert!(!motif.is_match(b"AC", MotifDistance::Levenshtein, 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_is_match() -> Result<(), Box<dyn std::error::Error>> {
+        let records = [Record::new("@fqlib:1/1", "TTAACGG", "+", "abcdefg")];
+
+        let filter = SequenceMotifFilter::new("CCGTT".parse()?, MotifDistance::Hamming, 0, false);
+        assert!(!filter.is_match(&records)?);
+
+        let filter = SequenceMotifFilter::new("CCGTT".parse()?, MotifDistance::Hamming, 0, true);
+        assert!(filter.is_match(&records)?);
+
+        Ok(())
+    }
+}
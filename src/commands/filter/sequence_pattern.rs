@@ -0,0 +1,45 @@
+use std::io;
+
+use regex::bytes::Regex;
+
+use super::{Predicate, PredicateKind};
+use crate::fastq::Record;
+
+/// A predicate that tests whether the record sequence matches a regular expression.
+pub struct SequencePatternFilter {
+    pattern: Regex,
+}
+
+impl SequencePatternFilter {
+    pub fn new(pattern: Regex) -> Self {
+        Self { pattern }
+    }
+}
+
+impl Predicate for SequencePatternFilter {
+    fn kind(&self) -> PredicateKind {
+        PredicateKind::SequencePattern
+    }
+
+    fn is_match(&self, records: &[Record]) -> io::Result<bool> {
+        Ok(self.pattern.is_match(records[0].sequence()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_match() -> io::Result<()> {
+        let filter = SequencePatternFilter::new(Regex::new("^TC").unwrap());
+
+        let records = [Record::new("@fqlib:1/1", "TCGA", "+", "dcba")];
+        assert!(filter.is_match(&records)?);
+
+        let records = [Record::new("@fqlib:2/1", "AGCT", "+", "abcd")];
+        assert!(!filter.is_match(&records)?);
+
+        Ok(())
+    }
+}
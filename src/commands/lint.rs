@@ -1,33 +1,65 @@
+mod parallel;
+mod report;
+
 use std::{
     io::{self, BufRead},
+    iter,
     path::{Path, PathBuf},
     process,
 };
 
 use thiserror::Error;
-use tracing::{error, info, info_span};
+use tracing::{error, info, info_span, warn};
+
+use self::{
+    parallel::{Failure, validate_records},
+    report::Report,
+};
 
 use crate::{
     cli::LintArgs,
-    fastq::{self, Record, io::SplitReader},
+    fastq::{self, Record, fs::ReadOptions, io::SplitReader},
     validators::{
-        self, LintMode, SingleReadValidatorMut, ValidationLevel, single::DuplicateNameValidator,
+        self, LintMode, SingleReadValidatorMut, ValidationLevel,
+        single::{
+            Alphabet, DuplicateNameMode, DuplicateNameValidator, QualityEncodingDetector,
+            QualityEncodingMode,
+        },
     },
 };
 
-fn exit_with_validation_error<P>(err: validators::Error, src: P, record_no: usize) -> !
+fn exit_with_validation_error<P>(
+    report: &mut Option<Report>,
+    err: validators::Error,
+    src: P,
+    record_no: usize,
+) -> !
 where
     P: AsRef<Path>,
 {
-    log_validation_error(err, src, record_no);
+    log_validation_error(report, err, src, record_no);
+
+    if let Some(report) = report {
+        report.set_record_count(record_no + 1);
+        write_report(report);
+    }
+
     info!(lint_mode = debug(LintMode::Panic), "exiting");
     process::exit(1);
 }
 
-fn log_validation_error<P>(err: validators::Error, src: P, record_no: usize)
-where
+fn log_validation_error<P>(
+    report: &mut Option<Report>,
+    err: validators::Error,
+    src: P,
+    record_no: usize,
+) where
     P: AsRef<Path>,
 {
+    if let Some(report) = report {
+        report.add_error(&err, src.as_ref(), record_no);
+    }
+
     let src = src.as_ref().display();
     let line_no = err.line_no(record_no);
 
@@ -43,6 +75,7 @@ where
 
 fn handle_validation_error<P>(
     lint_mode: LintMode,
+    report: &mut Option<Report>,
     error: validators::Error,
     pathname: P,
     record_counter: usize,
@@ -50,45 +83,164 @@ fn handle_validation_error<P>(
     P: AsRef<Path>,
 {
     match lint_mode {
-        LintMode::Panic => exit_with_validation_error(error, pathname, record_counter),
-        LintMode::Log => log_validation_error(error, pathname, record_counter),
+        LintMode::Panic => exit_with_validation_error(report, error, pathname, record_counter),
+        LintMode::Log => log_validation_error(report, error, pathname, record_counter),
     }
 }
 
+fn log_quality_encodings(
+    report: &mut Option<Report>,
+    detector: &QualityEncodingDetector,
+    src: &Path,
+    src_index: usize,
+) {
+    let encodings = detector.consistent_encodings();
+    let src_display = src.display();
+
+    if let Some(range) = detector.range() {
+        let min = char::from(*range.start());
+        let max = char::from(*range.end());
+
+        if encodings.is_empty() {
+            warn!(
+                src = display(src_display),
+                min = debug(min),
+                max = debug(max),
+                "no quality score encoding is consistent with the observed quality scores"
+            );
+        } else {
+            let names: Vec<String> = encodings.iter().map(|e| e.to_string()).collect();
+
+            info!(
+                src = display(src_display),
+                min = debug(min),
+                max = debug(max),
+                "consistent quality score encodings: {names:?}"
+            );
+        }
+    } else {
+        info!(src = display(src_display), "no quality scores observed");
+    }
+
+    if let Some(report) = report {
+        report.set_quality_encodings(src_index, &encodings);
+    }
+}
+
+fn write_report(report: &mut Report) {
+    if let Err(e) = report.write() {
+        error!(
+            dst = display(report.dst().display()),
+            "could not write report: {e}"
+        );
+    } else {
+        info!(dst = display(report.dst().display()), "wrote report");
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn validate_single(
     mut reader: fastq::io::Reader<impl BufRead>,
     record_definition_separator: Option<u8>,
     single_read_validation_level: ValidationLevel,
     disabled_validators: &[String],
+    enabled_validators: &[String],
+    alphabet: &Alphabet,
+    quality_encoding: Option<QualityEncodingMode>,
+    duplicate_name_mode: DuplicateNameMode,
     lint_mode: LintMode,
+    report: &mut Option<Report>,
     r1_src: &Path,
+    read_options: ReadOptions,
 ) -> Result<usize, LintError> {
-    let (single_read_validators, _) =
-        validators::filter_validators(single_read_validation_level, None, disabled_validators);
+    let (single_read_validators, _) = validators::filter_validators(
+        single_read_validation_level,
+        None,
+        disabled_validators,
+        enabled_validators,
+        alphabet,
+        quality_encoding.and_then(QualityEncodingMode::encoding),
+    );
 
-    let span = info_span!("validate_single");
-    let _span_ctx = span.enter();
+    let detector =
+        (quality_encoding == Some(QualityEncodingMode::Auto)).then(QualityEncodingDetector::new);
+
+    if let Some(report) = report {
+        report.add_single_read_validators(&single_read_validators);
+    }
+
+    let mut duplicate_name_validator =
+        build_duplicate_name_validator(disabled_validators, duplicate_name_mode, report, r1_src);
+
+    let span = info_span!("validate_single", pass = 1);
+    let span_ctx = span.enter();
 
     info!("start");
 
-    let mut record = Record::default();
-    let mut record_counter = 0;
     let mut failure_count = 0;
 
-    while reader.read_record(&mut record)? != 0 {
-        record.reset(record_definition_separator);
+    let record_counter = validate_records(
+        read_options.threads,
+        || {
+            let mut record = Record::default();
 
-        for validator in &single_read_validators {
-            if let Err(e) = validator.validate(&record) {
-                failure_count += 1;
-                handle_validation_error(lint_mode, e, r1_src, record_counter);
+            if reader.read_record(&mut record)? == 0 {
+                return Ok(None);
             }
-        }
 
-        record_counter += 1;
-    }
+            record.reset(record_definition_separator);
+
+            if let Some(validator) = &mut duplicate_name_validator {
+                validator.insert(&record)?;
+            }
+
+            Ok(Some(record))
+        },
+        |record, record_no, failures| {
+            if let Some(detector) = &detector {
+                detector.observe(record);
+            }
+
+            for validator in &single_read_validators {
+                if let Err(error) = validator.validate(record) {
+                    failures.push(Failure {
+                        record_no,
+                        src_index: 0,
+                        error,
+                    });
+                }
+            }
+        },
+        |failure| {
+            failure_count += 1;
+            handle_validation_error(lint_mode, report, failure.error, r1_src, failure.record_no);
+        },
+    )?;
 
     info!(record_count = record_counter, "end");
+    drop(span_ctx);
+
+    if let Some(report) = report {
+        report.set_record_count(record_counter);
+    }
+
+    if let Some(detector) = &detector {
+        log_quality_encodings(report, detector, r1_src, 0);
+    }
+
+    if let Some(validator) = duplicate_name_validator {
+        let span = info_span!("validate_single", pass = 2);
+        let _span_ctx = span.enter();
+
+        failure_count += validate_duplicate_names(
+            validator,
+            record_definition_separator,
+            lint_mode,
+            report,
+            r1_src,
+            read_options,
+        )?;
+    }
 
     Ok(failure_count)
 }
@@ -100,9 +252,15 @@ fn validate_pair<R>(
     single_read_validation_level: ValidationLevel,
     paired_read_validation_level: ValidationLevel,
     disabled_validators: &[String],
+    enabled_validators: &[String],
+    alphabet: &Alphabet,
+    quality_encoding: Option<QualityEncodingMode>,
+    duplicate_name_mode: DuplicateNameMode,
     lint_mode: LintMode,
+    report: &mut Option<Report>,
     r1_src: &Path,
     r2_src: &Path,
+    read_options: ReadOptions,
 ) -> Result<usize, LintError>
 where
     R: BufRead,
@@ -111,97 +269,192 @@ where
         single_read_validation_level,
         Some(paired_read_validation_level),
         disabled_validators,
+        enabled_validators,
+        alphabet,
+        quality_encoding.and_then(QualityEncodingMode::encoding),
     );
 
-    let mut duplicate_name_validator = DuplicateNameValidator::new();
+    let detectors = (quality_encoding == Some(QualityEncodingMode::Auto)).then(|| {
+        [
+            QualityEncodingDetector::new(),
+            QualityEncodingDetector::new(),
+        ]
+    });
 
-    let code = duplicate_name_validator.code();
-    let name = duplicate_name_validator.name();
-    let use_special_validator = !disabled_validators.contains(&code.to_string());
-
-    let validators = if use_special_validator {
-        format!(r#""[{code}] {name}""#)
-    } else {
-        String::new()
-    };
+    if let Some(report) = report {
+        report.add_single_read_validators(&single_read_validators);
+        report.add_paired_read_validators(&paired_read_validators);
+    }
 
-    info!("enabled special validators: [{}]", validators);
+    let mut duplicate_name_validator =
+        build_duplicate_name_validator(disabled_validators, duplicate_name_mode, report, r1_src);
 
     let span = info_span!("validate_pair", pass = 1);
     let span_ctx = span.enter();
 
     info!("start");
 
-    let mut records = [Record::default(), Record::default()];
-
-    let mut record_counter = 0;
+    let srcs = [r1_src, r2_src];
     let mut failure_count = 0;
 
-    loop {
-        match reader.read_records(&mut records)? {
-            [0, 0] => break,
-            [0, len] if len > 0 => return Err(LintError::UnexpectedEof("r1-src")),
-            [len, 0] if len > 0 => return Err(LintError::UnexpectedEof("r2-src")),
-            [_, _] => {}
-        }
+    let record_counter = validate_records(
+        read_options.threads,
+        || {
+            let mut records = [Record::default(), Record::default()];
 
-        records[0].reset(record_definition_separator);
-        records[1].reset(record_definition_separator);
+            match reader.read_records(&mut records)? {
+                [0, 0] => return Ok(None),
+                [0, len] if len > 0 => return Err(LintError::UnexpectedEof("r1-src")),
+                [len, 0] if len > 0 => return Err(LintError::UnexpectedEof("r2-src")),
+                [_, _] => {}
+            }
 
-        if use_special_validator {
-            duplicate_name_validator.insert(&records[0]);
-        }
+            records[0].reset(record_definition_separator);
+            records[1].reset(record_definition_separator);
 
-        for validator in &single_read_validators {
-            validator.validate(&records[0]).unwrap_or_else(|e| {
-                failure_count += 1;
-                handle_validation_error(lint_mode, e, r1_src, record_counter);
-            });
+            if let Some(validator) = &mut duplicate_name_validator {
+                validator.insert(&records[0])?;
+            }
 
-            validator.validate(&records[1]).unwrap_or_else(|e| {
-                failure_count += 1;
-                handle_validation_error(lint_mode, e, r2_src, record_counter);
-            });
-        }
+            Ok(Some(records))
+        },
+        |records, record_no, failures| {
+            if let Some(detectors) = &detectors {
+                for (detector, record) in detectors.iter().zip(records) {
+                    detector.observe(record);
+                }
+            }
 
-        for validator in &paired_read_validators {
-            validator
-                .validate(&records[0], &records[1])
-                .unwrap_or_else(|e| {
-                    failure_count += 1;
-                    handle_validation_error(lint_mode, e, r1_src, record_counter);
-                });
-        }
+            for validator in &single_read_validators {
+                for (src_index, record) in records.iter().enumerate() {
+                    if let Err(error) = validator.validate(record) {
+                        failures.push(Failure {
+                            record_no,
+                            src_index,
+                            error,
+                        });
+                    }
+                }
+            }
 
-        record_counter += 1;
-    }
+            for validator in &paired_read_validators {
+                if let Err(error) = validator.validate(&records[0], &records[1]) {
+                    failures.push(Failure {
+                        record_no,
+                        src_index: 0,
+                        error,
+                    });
+                }
+            }
+        },
+        |failure| {
+            failure_count += 1;
+            let src = srcs[failure.src_index];
+            handle_validation_error(lint_mode, report, failure.error, src, failure.record_no);
+        },
+    )?;
 
     info!(record_count = record_counter, "end");
     drop(span_ctx);
 
-    let span = info_span!("validate_pair", pass = 2);
-    let _span_ctx = span.enter();
+    if let Some(report) = report {
+        report.set_record_count(record_counter);
+    }
 
-    info!("start");
+    if let Some(detectors) = &detectors {
+        for (src_index, (detector, src)) in detectors.iter().zip(srcs).enumerate() {
+            log_quality_encodings(report, detector, src, src_index);
+        }
+    }
+
+    if let Some(validator) = duplicate_name_validator {
+        let span = info_span!("validate_pair", pass = 2);
+        let _span_ctx = span.enter();
+
+        failure_count += validate_duplicate_names(
+            validator,
+            record_definition_separator,
+            lint_mode,
+            report,
+            r1_src,
+            read_options,
+        )?;
+    }
+
+    Ok(failure_count)
+}
+
+/// Builds S007 (DuplicateNameValidator), unless it is disabled or r1-src cannot be read twice.
+fn build_duplicate_name_validator(
+    disabled_validators: &[String],
+    mode: DuplicateNameMode,
+    report: &mut Option<Report>,
+    r1_src: &Path,
+) -> Option<DuplicateNameValidator> {
+    let validator = DuplicateNameValidator::with_mode(mode);
+
+    let code = validator.code();
+    let name = validator.name();
+    let mut use_special_validator = !disabled_validators.contains(&code.to_string());
+
+    if use_special_validator && fastq::fs::is_stdio(r1_src) {
+        warn!("disabling [{code}] {name}: r1-src cannot be read twice from stdin");
+        use_special_validator = false;
+    }
+
+    let validators = if use_special_validator {
+        format!(r#""[{code}] {name}""#)
+    } else {
+        String::new()
+    };
+
+    info!("enabled special validators: [{}]", validators);
 
     if !use_special_validator {
-        return Ok(failure_count);
+        return None;
+    }
+
+    if let Some(report) = report {
+        report.add_special_validator(code, name);
+    }
+
+    Some(validator)
+}
+
+/// Runs the second pass of S007 (DuplicateNameValidator) over r1-src.
+///
+/// This returns the number of duplicate names.
+fn validate_duplicate_names(
+    mut validator: DuplicateNameValidator,
+    record_definition_separator: Option<u8>,
+    lint_mode: LintMode,
+    report: &mut Option<Report>,
+    r1_src: &Path,
+    read_options: ReadOptions,
+) -> Result<usize, LintError> {
+    info!("start");
+
+    validator.finish()?;
+
+    if validator.is_empty() {
+        info!("no possible duplicate names");
+        return Ok(0);
     }
 
-    let mut reader = fastq::fs::open(r1_src).map_err(|e| LintError::OpenFile(e, r1_src.into()))?;
+    let mut reader = fastq::fs::open_with_options(r1_src, read_options)
+        .map_err(|e| LintError::OpenFile(e, r1_src.into()))?;
 
     let mut record = Record::default();
     let mut record_counter = 0;
+    let mut failure_count = 0;
 
     while reader.read_record(&mut record)? != 0 {
         record.reset(record_definition_separator);
 
-        duplicate_name_validator
-            .validate(&record)
-            .unwrap_or_else(|e| {
-                failure_count += 1;
-                handle_validation_error(lint_mode, e, r1_src, record_counter);
-            });
+        validator.validate(&record).unwrap_or_else(|e| {
+            failure_count += 1;
+            handle_validation_error(lint_mode, report, e, r1_src, record_counter);
+        });
 
         record_counter += 1;
     }
@@ -211,7 +464,8 @@ where
     Ok(failure_count)
 }
 
-pub fn lint(args: LintArgs) -> Result<(), LintError> {
+/// Opens the sources and validates them, returning the number of validation errors.
+fn validate_sources(args: &LintArgs, report: &mut Option<Report>) -> Result<usize, LintError> {
     let lint_mode = args.lint_mode;
 
     let r1_src = &args.r1_src;
@@ -224,12 +478,16 @@ pub fn lint(args: LintArgs) -> Result<(), LintError> {
 
     let record_definition_separator = args.record_definition_separator.map(u8::from);
 
-    info!(command = "lint", "fq");
+    let read_options = ReadOptions {
+        threads: args.threads,
+    };
 
-    let r1 = fastq::fs::open(r1_src).map_err(|e| LintError::OpenFile(e, r1_src.into()))?;
+    let r1 = fastq::fs::open_with_options(r1_src, read_options)
+        .map_err(|e| LintError::OpenFile(e, r1_src.into()))?;
 
-    let failure_count = if let Some(r2_src) = r2_src {
-        let r2 = fastq::fs::open(r2_src).map_err(|e| LintError::OpenFile(e, r2_src.into()))?;
+    if let Some(r2_src) = r2_src {
+        let r2 = fastq::fs::open_with_options(r2_src, read_options)
+            .map_err(|e| LintError::OpenFile(e, r2_src.into()))?;
         let reader = SplitReader::new([r1, r2]);
 
         validate_pair(
@@ -238,20 +496,69 @@ pub fn lint(args: LintArgs) -> Result<(), LintError> {
             single_read_validation_level,
             paired_read_validation_level,
             disabled_validators,
+            &args.enable_validator,
+            &args.alphabet,
+            args.quality_encoding,
+            args.duplicate_name_mode,
             lint_mode,
+            report,
             r1_src,
             r2_src,
-        )?
+            read_options,
+        )
     } else {
         validate_single(
             r1,
             record_definition_separator,
             single_read_validation_level,
             disabled_validators,
+            &args.enable_validator,
+            &args.alphabet,
+            args.quality_encoding,
+            args.duplicate_name_mode,
             lint_mode,
+            report,
             r1_src,
-        )?
-    };
+            read_options,
+        )
+    }
+}
+
+pub fn lint(args: LintArgs) -> Result<(), LintError> {
+    let r1_src = &args.r1_src;
+    let r2_src = args.r2_src.as_ref();
+
+    info!(command = "lint", "fq");
+
+    ensure_single_stdio(r1_src, r2_src.map(|p| p.as_path()))?;
+    ensure_opt_in_validators(&args.enable_validator)?;
+
+    let mut report = args.report.as_ref().map(|dst| {
+        let mut srcs = vec![r1_src.as_path()];
+        srcs.extend(r2_src.map(|p| p.as_path()));
+
+        let mut report = Report::new(dst, &srcs);
+        report.set_max_errors((args.report_max_errors > 0).then_some(args.report_max_errors));
+        report
+    });
+
+    let result = validate_sources(&args, &mut report);
+
+    // A partial report is still written when linting stops early.
+    if let Err(e) = &result
+        && let Some(report) = report.as_mut()
+    {
+        report.set_fatal_error(e);
+        write_report(report);
+    }
+
+    let failure_count = result?;
+
+    if let Some(report) = report.as_mut() {
+        report
+            .write()
+            .map_err(|e| LintError::WriteReport(e, report.dst().into()))?;
+    }
 
     info!("done");
 
@@ -262,6 +569,24 @@ pub fn lint(args: LintArgs) -> Result<(), LintError> {
     Ok(())
 }
 
+fn ensure_single_stdio(r1_src: &Path, r2_src: Option<&Path>) -> Result<(), LintError> {
+    if fastq::fs::has_multiple_stdio(iter::once(r1_src).chain(r2_src)) {
+        Err(LintError::MultipleStdinSources)
+    } else {
+        Ok(())
+    }
+}
+
+fn ensure_opt_in_validators(enabled_validators: &[String]) -> Result<(), LintError> {
+    match enabled_validators
+        .iter()
+        .find(|code| !validators::OPT_IN_VALIDATOR_CODES.contains(&code.as_str()))
+    {
+        Some(code) => Err(LintError::InvalidEnabledValidator(code.clone())),
+        None => Ok(()),
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum LintError {
     #[error("I/O error")]
@@ -272,4 +597,46 @@ pub enum LintError {
     CreateFile(#[source] io::Error, PathBuf),
     #[error("{0} unexpectedly ended")]
     UnexpectedEof(&'static str),
+    #[error("could not write report: {1}")]
+    WriteReport(#[source] io::Error, PathBuf),
+    #[error("stdin (`-`) can only be used as one source")]
+    MultipleStdinSources,
+    #[error(
+        "invalid enabled validator: {0} (expected one of: {codes})",
+        codes = validators::OPT_IN_VALIDATOR_CODES.join(", ")
+    )]
+    InvalidEnabledValidator(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ensure_single_stdio() {
+        assert!(ensure_single_stdio(Path::new("-"), None).is_ok());
+        assert!(ensure_single_stdio(Path::new("-"), Some(Path::new("r2.fq"))).is_ok());
+        assert!(ensure_single_stdio(Path::new("r1.fq"), Some(Path::new("-"))).is_ok());
+
+        assert!(matches!(
+            ensure_single_stdio(Path::new("-"), Some(Path::new("-"))),
+            Err(LintError::MultipleStdinSources)
+        ));
+    }
+
+    #[test]
+    fn test_ensure_opt_in_validators() {
+        assert!(ensure_opt_in_validators(&[]).is_ok());
+        assert!(ensure_opt_in_validators(&[String::from("S009"), String::from("P002")]).is_ok());
+
+        assert!(matches!(
+            ensure_opt_in_validators(&[String::from("S001")]),
+            Err(LintError::InvalidEnabledValidator(code)) if code == "S001"
+        ));
+
+        assert!(matches!(
+            ensure_opt_in_validators(&[String::from("P002"), String::from("S099")]),
+            Err(LintError::InvalidEnabledValidator(code)) if code == "S099"
+        ));
+    }
 }
@@ -0,0 +1,221 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    num::NonZeroUsize,
+    sync::{Mutex, mpsc},
+    thread,
+};
+
+use super::LintError;
+use crate::validators;
+
+// The number of records (or pairs) sent to a worker at a time.
+const BATCH_SIZE: usize = 4096;
+
+// The number of batches each worker can have queued.
+const QUEUE_DEPTH_PER_THREAD: usize = 2;
+
+/// A validation failure.
+#[derive(Debug)]
+pub(super) struct Failure {
+    /// The 0-based record number.
+    pub record_no: usize,
+    /// The index of the source the failing record was read from, e.g., 1 for read 2.
+    pub src_index: usize,
+    pub error: validators::Error,
+}
+
+/// Validates records read using `next`, calling `handle` for each failure in record order.
+///
+/// `validate` is given each record (or pair) and its record number and adds any failures to the
+/// given list. When given more than one thread, records are read on the calling thread and
+/// validated in batches on a pool of worker threads.
+///
+/// The first record is always validated before any others, so validators that compare records to
+/// the first, e.g., S009, report the same errors regardless of the number of threads.
+///
+/// This returns the number of records (or pairs) read.
+pub(super) fn validate_records<T, N, V, H>(
+    threads: NonZeroUsize,
+    mut next: N,
+    validate: V,
+    mut handle: H,
+) -> Result<usize, LintError>
+where
+    T: Send,
+    N: FnMut() -> Result<Option<T>, LintError>,
+    V: Fn(&T, usize, &mut Vec<Failure>) + Sync,
+    H: FnMut(Failure),
+{
+    if threads.get() == 1 {
+        let mut failures = Vec::new();
+        let mut record_no = 0;
+
+        while let Some(item) = next()? {
+            validate(&item, record_no, &mut failures);
+            failures.drain(..).for_each(&mut handle);
+            record_no += 1;
+        }
+
+        return Ok(record_no);
+    }
+
+    let Some(first_item) = next()? else {
+        return Ok(0);
+    };
+
+    let mut failures = Vec::new();
+    validate(&first_item, 0, &mut failures);
+    failures.into_iter().for_each(&mut handle);
+
+    let (batch_tx, batch_rx) =
+        mpsc::sync_channel::<(usize, usize, Vec<T>)>(threads.get() * QUEUE_DEPTH_PER_THREAD);
+    let batch_rx = Mutex::new(batch_rx);
+    let (failures_tx, failures_rx) = mpsc::channel::<(usize, Vec<Failure>)>();
+
+    thread::scope(|scope| {
+        for _ in 0..threads.get() {
+            let batch_rx = &batch_rx;
+            let validate = &validate;
+            let failures_tx = failures_tx.clone();
+
+            scope.spawn(move || {
+                loop {
+                    let batch = match batch_rx.lock() {
+                        Ok(rx) => rx.recv(),
+                        Err(_) => break,
+                    };
+
+                    let Ok((i, start, items)) = batch else {
+                        break;
+                    };
+
+                    let mut failures = Vec::new();
+
+                    for (j, item) in items.iter().enumerate() {
+                        validate(item, start + j, &mut failures);
+                    }
+
+                    if failures_tx.send((i, failures)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        drop(failures_tx);
+
+        // Results can arrive out of order, so they are held until all earlier batches are handled.
+        let mut results = BTreeMap::new();
+        let mut next_batch_index = 0;
+
+        let mut handle_ready = |results: &mut BTreeMap<usize, Vec<Failure>>| {
+            while let Some(failures) = results.remove(&next_batch_index) {
+                failures.into_iter().for_each(&mut handle);
+                next_batch_index += 1;
+            }
+        };
+
+        let mut batch_count = 0;
+        let mut record_no = 1;
+
+        loop {
+            let mut batch = Vec::with_capacity(BATCH_SIZE);
+
+            while batch.len() < BATCH_SIZE {
+                match next()? {
+                    Some(item) => batch.push(item),
+                    None => break,
+                }
+            }
+
+            let is_last_batch = batch.len() < BATCH_SIZE;
+
+            if !batch.is_empty() {
+                let len = batch.len();
+
+                batch_tx
+                    .send((batch_count, record_no, batch))
+                    .map_err(|_| io::Error::other("lint workers unexpectedly stopped"))?;
+
+                batch_count += 1;
+                record_no += len;
+            }
+
+            results.extend(failures_rx.try_iter());
+            handle_ready(&mut results);
+
+            if is_last_batch {
+                break;
+            }
+        }
+
+        drop(batch_tx);
+
+        for (i, failures) in failures_rx {
+            results.insert(i, failures);
+            handle_ready(&mut results);
+        }
+
+        Ok(record_no)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validators::LineType;
+
+    #[test]
+    fn test_validate_records() -> Result<(), LintError> {
+        const N: usize = 10_000;
+
+        for threads in [1, 4] {
+            let threads = NonZeroUsize::new(threads).unwrap();
+
+            let mut items = 0..N;
+            let mut record_nos = Vec::new();
+
+            let record_count = validate_records(
+                threads,
+                || Ok(items.next()),
+                |&item, record_no, failures| {
+                    assert_eq!(item, record_no);
+
+                    if item % 1000 == 0 {
+                        failures.push(Failure {
+                            record_no,
+                            src_index: 0,
+                            error: validators::Error::new("T001", "Test", "", LineType::Name, None),
+                        });
+                    }
+                },
+                |failure| record_nos.push(failure.record_no),
+            )?;
+
+            assert_eq!(record_count, N);
+            assert_eq!(record_nos, (0..N).step_by(1000).collect::<Vec<_>>());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_records_validates_first_record_first() -> Result<(), LintError> {
+        let mut items = 0..10_000;
+        let order = Mutex::new(Vec::new());
+
+        validate_records(
+            NonZeroUsize::new(4).unwrap(),
+            || Ok(items.next()),
+            |&item, _, _| order.lock().unwrap().push(item),
+            |_| {},
+        )?;
+
+        let order = order.into_inner().unwrap();
+        assert_eq!(order.len(), 10_000);
+        assert_eq!(order[0], 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,333 @@
+//! Machine-readable lint report.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{
+    fastq,
+    validators::{self, PairedReadValidator, QualityEncoding, SingleReadValidator},
+};
+
+/// A summary of a lint run that is serialized as JSON.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    #[serde(skip)]
+    dst: PathBuf,
+    #[serde(skip)]
+    max_errors: Option<usize>,
+    sources: Vec<Source>,
+    validators: Vec<Validator>,
+    failure_counts: BTreeMap<String, usize>,
+    errors: Vec<Error>,
+    errors_omitted: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fatal_error: Option<String>,
+    passed: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Source {
+    src: PathBuf,
+    record_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quality_encodings: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+struct Validator {
+    code: &'static str,
+    name: &'static str,
+    kind: ValidatorKind,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ValidatorKind {
+    SingleRead,
+    PairedRead,
+}
+
+#[derive(Debug, Serialize)]
+struct Error {
+    src: PathBuf,
+    line_no: usize,
+    col_no: Option<usize>,
+    validator_code: String,
+    validator_name: String,
+    message: String,
+}
+
+impl Report {
+    /// Creates a report that is written to `dst` for the given sources.
+    pub fn new<P>(dst: P, srcs: &[&Path]) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self {
+            dst: dst.into(),
+            max_errors: None,
+            sources: srcs
+                .iter()
+                .map(|src| Source {
+                    src: src.to_path_buf(),
+                    record_count: 0,
+                    quality_encodings: None,
+                })
+                .collect(),
+            validators: Vec::new(),
+            failure_counts: BTreeMap::new(),
+            errors: Vec::new(),
+            errors_omitted: 0,
+            fatal_error: None,
+            passed: true,
+        }
+    }
+
+    /// Sets the maximum number of validation errors kept in the report.
+    ///
+    /// `None` keeps all errors. Errors past the limit are only counted in the failure counts and
+    /// `errors_omitted`.
+    pub fn set_max_errors(&mut self, max_errors: Option<usize>) {
+        self.max_errors = max_errors;
+    }
+
+    /// Adds the single read validators that were enabled.
+    pub fn add_single_read_validators(&mut self, validators: &[Box<dyn SingleReadValidator>]) {
+        for validator in validators {
+            self.add_validator(
+                validator.code(),
+                validator.name(),
+                ValidatorKind::SingleRead,
+            );
+        }
+    }
+
+    /// Adds the paired read validators that were enabled.
+    pub fn add_paired_read_validators(&mut self, validators: &[Box<dyn PairedReadValidator>]) {
+        for validator in validators {
+            self.add_validator(
+                validator.code(),
+                validator.name(),
+                ValidatorKind::PairedRead,
+            );
+        }
+    }
+
+    /// Adds a single read validator that is not part of the standard set, e.g., S007.
+    pub fn add_special_validator(&mut self, code: &'static str, name: &'static str) {
+        self.add_validator(code, name, ValidatorKind::SingleRead);
+    }
+
+    fn add_validator(&mut self, code: &'static str, name: &'static str, kind: ValidatorKind) {
+        self.validators.push(Validator { code, name, kind });
+    }
+
+    /// Records a validation error for the record at `record_no` in `src`.
+    ///
+    /// Errors past the maximum (see [`Self::set_max_errors`]) are counted but not stored.
+    pub fn add_error(&mut self, err: &validators::Error, src: &Path, record_no: usize) {
+        *self.failure_counts.entry(err.code.clone()).or_default() += 1;
+
+        if self.max_errors.is_some_and(|n| self.errors.len() >= n) {
+            self.errors_omitted += 1;
+            return;
+        }
+
+        self.errors.push(Error {
+            src: src.to_path_buf(),
+            line_no: err.line_no(record_no),
+            col_no: err.col_no,
+            validator_code: err.code.clone(),
+            validator_name: err.name.clone(),
+            message: err.to_string(),
+        });
+    }
+
+    /// Sets the number of records read from all sources.
+    ///
+    /// This never decreases a previously set count, so it is safe to call when exiting early.
+    pub fn set_record_count(&mut self, record_count: usize) {
+        for source in &mut self.sources {
+            source.record_count = source.record_count.max(record_count);
+        }
+    }
+
+    /// Sets the quality score encodings that are consistent with the source at `src_index`.
+    pub fn set_quality_encodings(&mut self, src_index: usize, encodings: &[QualityEncoding]) {
+        if let Some(source) = self.sources.get_mut(src_index) {
+            source.quality_encodings = Some(encodings.iter().map(|e| e.to_string()).collect());
+        }
+    }
+
+    /// Records an error that stopped linting before all records were validated.
+    ///
+    /// A report with a fatal error never passes.
+    pub fn set_fatal_error<E>(&mut self, err: &E)
+    where
+        E: std::error::Error,
+    {
+        let mut message = err.to_string();
+        let mut source = err.source();
+
+        while let Some(e) = source {
+            message.push_str(": ");
+            message.push_str(&e.to_string());
+            source = e.source();
+        }
+
+        self.fatal_error = Some(message);
+    }
+
+    /// Returns the destination of the report.
+    pub fn dst(&self) -> &Path {
+        &self.dst
+    }
+
+    /// Sets the final verdict and writes the report as JSON to its destination.
+    ///
+    /// If the destination is `-`, the report is written to stdout.
+    pub fn write(&mut self) -> io::Result<()> {
+        self.passed = self.fatal_error.is_none() && self.failure_counts.is_empty();
+
+        let mut writer: Box<dyn Write> = if fastq::fs::is_stdio(&self.dst) {
+            Box::new(BufWriter::new(io::stdout().lock()))
+        } else {
+            Box::new(File::create(&self.dst).map(BufWriter::new)?)
+        };
+
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writeln!(writer)?;
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validators::LineType;
+
+    #[test]
+    fn test_add_error() {
+        let mut report = Report::new("report.json", &[Path::new("r1.fastq")]);
+        assert!(report.failure_counts.is_empty());
+
+        let err = validators::Error::new(
+            "S003",
+            "NameValidator",
+            "missing @ prefix",
+            LineType::Name,
+            Some(1),
+        );
+
+        report.add_error(&err, Path::new("r1.fastq"), 2);
+        report.add_error(&err, Path::new("r1.fastq"), 5);
+
+        assert_eq!(report.failure_counts.get("S003"), Some(&2));
+        assert_eq!(report.errors.len(), 2);
+        assert_eq!(report.errors[0].line_no, 9);
+        assert_eq!(report.errors[1].line_no, 21);
+        assert_eq!(report.errors_omitted, 0);
+    }
+
+    #[test]
+    fn test_add_error_with_max_errors() {
+        const MAX_ERRORS: usize = 4;
+
+        let mut report = Report::new("report.json", &[Path::new("r1.fastq")]);
+        report.set_max_errors(Some(MAX_ERRORS));
+
+        let err = validators::Error::new(
+            "S002",
+            "AlphabetValidator",
+            "invalid character",
+            LineType::Sequence,
+            Some(1),
+        );
+
+        for record_no in 0..MAX_ERRORS + 3 {
+            report.add_error(&err, Path::new("r1.fastq"), record_no);
+        }
+
+        assert_eq!(report.failure_counts.get("S002"), Some(&(MAX_ERRORS + 3)));
+        assert_eq!(report.errors.len(), MAX_ERRORS);
+        assert_eq!(report.errors_omitted, 3);
+    }
+
+    #[test]
+    fn test_set_fatal_error() -> serde_json::Result<()> {
+        let mut report = Report::new("report.json", &[Path::new("r1.fastq")]);
+
+        let err = io::Error::new(io::ErrorKind::UnexpectedEof, "r2-src unexpectedly ended");
+        report.set_fatal_error(&err);
+
+        assert!(report.fatal_error.is_some());
+
+        let actual = serde_json::to_value(&report)?;
+        assert_eq!(actual["fatal_error"], "r2-src unexpectedly ended");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_record_count() {
+        let mut report = Report::new(
+            "report.json",
+            &[Path::new("r1.fastq"), Path::new("r2.fastq")],
+        );
+
+        report.set_record_count(8);
+        report.set_record_count(3);
+
+        assert!(report.sources.iter().all(|source| source.record_count == 8));
+    }
+
+    #[test]
+    fn test_set_quality_encodings() -> serde_json::Result<()> {
+        let mut report = Report::new("report.json", &[Path::new("r1.fastq")]);
+        report.set_quality_encodings(0, &[QualityEncoding::Phred64, QualityEncoding::Solexa]);
+
+        let actual = serde_json::to_value(&report.sources[0])?;
+
+        let expected = serde_json::json!({
+            "src": "r1.fastq",
+            "record_count": 0,
+            "quality_encodings": ["Phred+64", "Solexa+64"],
+        });
+
+        assert_eq!(actual, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize() -> serde_json::Result<()> {
+        let mut report = Report::new("report.json", &[Path::new("r1.fastq")]);
+        report.add_special_validator("S007", "DuplicateNameValidator");
+        report.set_record_count(1);
+
+        let actual: serde_json::Value = serde_json::to_value(&report)?;
+
+        let expected = serde_json::json!({
+            "sources": [{ "src": "r1.fastq", "record_count": 1 }],
+            "validators": [{
+                "code": "S007",
+                "name": "DuplicateNameValidator",
+                "kind": "single_read",
+            }],
+            "failure_counts": {},
+            "errors": [],
+            "errors_omitted": 0,
+            "passed": true,
+        });
+
+        assert_eq!(actual, expected);
+
+        Ok(())
+    }
+}
@@ -1,61 +1,158 @@
+mod replicates;
+
 use std::{
-    fs::File,
-    io::{self, BufRead, BufReader, Write},
+    io::{self, BufRead, Write},
+    iter,
     ops::{Bound, RangeBounds},
     path::{Path, PathBuf},
 };
 
 use bitvec::vec::BitVec;
-use flate2::bufread::MultiGzDecoder;
+use bytes::Bytes;
 use rand::{
-    SeedableRng,
+    Rng, SeedableRng,
     distr::{Distribution, Uniform},
     rngs::SmallRng,
 };
+use rapidhash::v3::{RapidSecrets, rapidhash_v3_seeded};
 use thiserror::Error;
 use tracing::{info, info_span, warn};
 
+use self::replicates::subsample_replicates;
 use crate::{
     cli::SubsampleArgs,
-    fastq::{self, Record},
+    fastq::{
+        self, Record,
+        fs::{ReadOptions, WriteOptions},
+    },
 };
 
 const VALID_PROBABILITY_RANGE: (Bound<f64>, Bound<f64>) =
     (Bound::Excluded(0.0), Bound::Excluded(1.0));
 
 pub fn subsample(args: SubsampleArgs) -> Result<(), SubsampleError> {
+    info!(command = "subsample", "fq");
+
+    ensure_single_stdio(
+        iter::once(&args.r1_src).chain(&args.r2_src),
+        args.r1_dst.iter().chain(&args.r2_dst),
+    )?;
+
+    let replicate_count = args.probability.len().max(args.record_count.len());
+
+    if replicate_count > 1 {
+        subsample_replicates(&args)?;
+        info!("done");
+        return Ok(());
+    }
+
+    if args.r1_dst.len() > 1 || args.r2_dst.len() > 1 {
+        return Err(SubsampleError::InvalidDestinationCount {
+            expected: 1,
+            actual: args.r1_dst.len().max(args.r2_dst.len()),
+        });
+    }
+
     let r1_src = &args.r1_src;
-    let r1_dst = &args.r1_dst;
+    let r1_dst = &args.r1_dst[0];
 
     let r2_src = args.r2_src.as_ref();
-    let r2_dst = args.r2_dst.as_ref();
+    let r2_dst = args.r2_dst.first();
 
-    info!(command = "subsample", "fq");
+    let read_options = ReadOptions {
+        threads: args.threads,
+    };
 
-    let rng = if let Some(seed) = args.seed {
-        info!(seed = seed, "initializing rng from seed");
-        SmallRng::seed_from_u64(seed)
-    } else {
-        info!("initializing rng from entropy");
-        SmallRng::from_os_rng()
+    let write_options = WriteOptions {
+        compression: args.output_compression,
+        compression_level: args.compression_level,
+        threads: args.threads,
     };
 
-    if let Some(probability) = args.probability {
+    let probability = args.probability.first().copied();
+    let record_count = args.record_count.first().copied();
+
+    if let Some(probability) = probability
+        && args.hash_by_name
+    {
+        let seed = args.seed.unwrap_or_default();
+        info!(seed = seed, "sampling by name hash");
+
+        let sampler = NameHashSampler::new(seed, probability);
+
+        subsample_approximate(
+            (r1_src, r1_dst),
+            (r2_src.map(|p| &**p), r2_dst.map(|p| &**p)),
+            probability,
+            |record| sampler.is_kept(record),
+            read_options,
+            write_options,
+        )?;
+    } else if let Some(probability) = probability {
+        let mut rng = build_rng(args.seed);
+
         subsample_approximate(
             (r1_src, r1_dst),
             (r2_src.map(|p| &**p), r2_dst.map(|p| &**p)),
-            rng,
             probability,
+            |_| rng.random::<f64>() <= probability,
+            read_options,
+            write_options,
         )?;
-    } else if let Some(record_count) = args.record_count {
+    } else if let Some(record_count) = record_count
+        && args.reservoir
+    {
+        subsample_reservoir(
+            (r1_src, r1_dst),
+            (r2_src.map(|p| &**p), r2_dst.map(|p| &**p)),
+            build_rng(args.seed),
+            record_count,
+            read_options,
+            write_options,
+        )?;
+    } else if let Some(record_count) = record_count {
         subsample_exact(
             (r1_src, r1_dst),
             (r2_src.map(|p| &**p), r2_dst.map(|p| &**p)),
-            rng,
+            build_rng(args.seed),
             record_count,
+            read_options,
+            write_options,
         )?;
     } else {
-        unreachable!();
+        let target_bases = match (args.target_bases, args.target_coverage, args.genome_size) {
+            (Some(target_bases), _, _) => target_bases,
+            (None, Some(target_coverage), Some(genome_size)) => {
+                if target_coverage.is_nan() || target_coverage <= 0.0 {
+                    return Err(SubsampleError::InvalidTargetCoverage(target_coverage));
+                }
+
+                let target_bases = (target_coverage * genome_size as f64).round() as u64;
+                info!(target_bases = target_bases, "calculated target bases");
+                target_bases
+            }
+            _ => unreachable!(),
+        };
+
+        if args.approximate {
+            subsample_bases_approximate(
+                (r1_src, r1_dst),
+                (r2_src.map(|p| &**p), r2_dst.map(|p| &**p)),
+                build_rng(args.seed),
+                target_bases,
+                read_options,
+                write_options,
+            )?;
+        } else {
+            subsample_bases(
+                (r1_src, r1_dst),
+                (r2_src.map(|p| &**p), r2_dst.map(|p| &**p)),
+                build_rng(args.seed),
+                target_bases,
+                read_options,
+                write_options,
+            )?;
+        }
     }
 
     info!("done");
@@ -63,66 +160,186 @@ pub fn subsample(args: SubsampleArgs) -> Result<(), SubsampleError> {
     Ok(())
 }
 
-fn subsample_approximate<Rng>(
+fn open(
+    src: &Path,
+    options: ReadOptions,
+) -> Result<fastq::io::Reader<Box<dyn BufRead>>, SubsampleError> {
+    fastq::fs::open_with_options(src, options).map_err(|e| SubsampleError::OpenFile(e, src.into()))
+}
+
+fn create(
+    dst: &Path,
+    options: WriteOptions,
+) -> Result<fastq::io::Writer<Box<dyn Write>>, SubsampleError> {
+    fastq::fs::create_with_options(dst, options)
+        .map_err(|e| SubsampleError::CreateFile(e, dst.into()))
+}
+
+fn build_rng(seed: Option<u64>) -> SmallRng {
+    if let Some(seed) = seed {
+        info!(seed = seed, "initializing rng from seed");
+        SmallRng::seed_from_u64(seed)
+    } else {
+        info!("initializing rng from entropy");
+        SmallRng::from_os_rng()
+    }
+}
+
+/// A sampler that keeps a record based on a hash of its name.
+///
+/// The hash is a function of only the name, after removing the description and read number
+/// (see [`Record::reset`]), and the seed. Mates, and any other copies of a record, are therefore
+/// always sampled identically.
+struct NameHashSampler {
+    secrets: RapidSecrets,
+    threshold: u64,
+}
+
+impl NameHashSampler {
+    fn new(seed: u64, probability: f64) -> Self {
+        // 2^64
+        const HASH_RANGE: f64 = 18_446_744_073_709_551_616.0;
+
+        Self {
+            secrets: RapidSecrets::seed(seed),
+            threshold: (probability * HASH_RANGE) as u64,
+        }
+    }
+
+    fn is_kept(&self, record: &Record) -> bool {
+        // Strips the description and read number suffix, like `Record::reset(None)`.
+        let name = record.name();
+        let name = name
+            .iter()
+            .position(|&b| b == b'/' || b == b' ')
+            .map_or(name, |i| &name[..i]);
+
+        rapidhash_v3_seeded(name, &self.secrets) < self.threshold
+    }
+}
+
+fn subsample_approximate<F>(
     (r1_src, r1_dst): (&Path, &Path),
     (r2_src, r2_dst): (Option<&Path>, Option<&Path>),
-    mut rng: Rng,
     probability: f64,
+    mut is_kept: F,
+    read_options: ReadOptions,
+    write_options: WriteOptions,
 ) -> Result<(), SubsampleError>
 where
-    Rng: rand::Rng,
+    F: FnMut(&Record) -> bool,
 {
     if !VALID_PROBABILITY_RANGE.contains(&probability) {
         return Err(SubsampleError::InvalidProbability(probability));
     }
 
-    let mut r1 = fastq::fs::open(r1_src).map_err(|e| SubsampleError::OpenFile(e, r1_src.into()))?;
-    let mut w1 =
-        fastq::fs::create(r1_dst).map_err(|e| SubsampleError::CreateFile(e, r1_dst.into()))?;
+    let r2 = r2_paths(r2_src, r2_dst)?;
 
     let span = info_span!("subsample_approximate", probability = probability);
     let _span_ctx = span.enter();
 
-    let (n, total) = match (r2_src, r2_dst) {
-        (Some(r2_src), Some(r2_dst)) => {
-            info!("sampling paired end reads");
-
-            let mut r2 =
-                fastq::fs::open(r2_src).map_err(|e| SubsampleError::OpenFile(e, r2_src.into()))?;
-            let mut w2 = fastq::fs::create(r2_dst)
-                .map_err(|e| SubsampleError::CreateFile(e, r2_dst.into()))?;
-
-            subsample_paired(
-                (&mut r1, &mut w1),
-                (&mut r2, &mut w2),
-                &mut rng,
-                probability,
-            )?
-        }
-        (Some(_), None) => return Err(SubsampleError::MissingDestination("r2-dst")),
-        (None, Some(_)) => return Err(SubsampleError::MissingSource("r2-src")),
-        _ => {
-            info!("sampling single end reads");
-            subsample_single(&mut r1, &mut w1, &mut rng, probability)?
-        }
-    };
+    let (n, total) = sample_by_predicate(
+        (r1_src, r1_dst),
+        r2,
+        &mut is_kept,
+        read_options,
+        write_options,
+    )?;
 
-    let percentage = (n as f64) / (total as f64) * 100.0;
-    info!("sampled {}/{} ({:.1}%) records", n, total, percentage);
+    info!(
+        "sampled {}/{} ({:.1}%) records",
+        n,
+        total,
+        percentage(n, total)
+    );
 
     Ok(())
 }
 
-fn subsample_single<R, W, Rng>(
+// Returns the second source and destination, if both are given.
+fn r2_paths<'a>(
+    r2_src: Option<&'a Path>,
+    r2_dst: Option<&'a Path>,
+) -> Result<Option<(&'a Path, &'a Path)>, SubsampleError> {
+    match (r2_src, r2_dst) {
+        (Some(r2_src), Some(r2_dst)) => Ok(Some((r2_src, r2_dst))),
+        (Some(_), None) => Err(SubsampleError::MissingDestination("r2-dst")),
+        (None, Some(_)) => Err(SubsampleError::MissingSource("r2-src")),
+        (None, None) => Ok(None),
+    }
+}
+
+// Returns `n` as a percentage of `total`, or 0 if `total` is 0.
+fn percentage(n: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        (n as f64) / (total as f64) * 100.0
+    }
+}
+
+/// Samples single or paired end records, deciding using `is_kept`.
+///
+/// This returns the number of records (or pairs) kept and the total number read.
+fn sample_by_predicate<F>(
+    (r1_src, r1_dst): (&Path, &Path),
+    r2: Option<(&Path, &Path)>,
+    is_kept: &mut F,
+    read_options: ReadOptions,
+    write_options: WriteOptions,
+) -> Result<(u64, u64), SubsampleError>
+where
+    F: FnMut(&Record) -> bool,
+{
+    let mut r1 = open(r1_src, read_options)?;
+    let mut w1 = create(r1_dst, write_options)?;
+
+    if let Some((r2_src, r2_dst)) = r2 {
+        info!("sampling paired end reads");
+
+        let mut r2 = open(r2_src, read_options)?;
+        let mut w2 = create(r2_dst, write_options)?;
+
+        subsample_paired((&mut r1, &mut w1), (&mut r2, &mut w2), is_kept)
+    } else {
+        info!("sampling single end reads");
+        subsample_single(&mut r1, &mut w1, is_kept)
+    }
+}
+
+/// Samples single or paired end records, keeping the records set in `bitmap`.
+fn sample_by_filter(
+    (r1_src, r1_dst): (&Path, &Path),
+    r2: Option<(&Path, &Path)>,
+    bitmap: &BitVec,
+    read_options: ReadOptions,
+    write_options: WriteOptions,
+) -> Result<(), SubsampleError> {
+    let mut r1 = open(r1_src, read_options)?;
+    let mut w1 = create(r1_dst, write_options)?;
+
+    if let Some((r2_src, r2_dst)) = r2 {
+        info!("sampling paired end reads");
+
+        let mut r2 = open(r2_src, read_options)?;
+        let mut w2 = create(r2_dst, write_options)?;
+
+        subsample_exact_paired((&mut r1, &mut w1), (&mut r2, &mut w2), bitmap)
+    } else {
+        info!("sampling single end reads");
+        subsample_exact_single(&mut r1, &mut w1, bitmap)
+    }
+}
+
+fn subsample_single<R, W, F>(
     reader: &mut fastq::io::Reader<R>,
     writer: &mut fastq::io::Writer<W>,
-    rng: &mut Rng,
-    p: f64,
+    is_kept: &mut F,
 ) -> Result<(u64, u64), SubsampleError>
 where
     R: BufRead,
     W: Write,
-    Rng: rand::Rng,
+    F: FnMut(&Record) -> bool,
 {
     let mut record = Record::default();
 
@@ -130,9 +347,7 @@ where
     let mut total = 0;
 
     while reader.read_record(&mut record)? != 0 {
-        let q: f64 = rng.random();
-
-        if q <= p {
+        if is_kept(&record) {
             writer.write_record(&record)?;
             n += 1;
         }
@@ -143,18 +358,18 @@ where
     Ok((n, total))
 }
 
-fn subsample_paired<R, S, W, X, Rng>(
+/// Samples pairs of records, deciding using the first record of each pair.
+fn subsample_paired<R, S, W, X, F>(
     (r1, w1): (&mut fastq::io::Reader<R>, &mut fastq::io::Writer<W>),
     (r2, w2): (&mut fastq::io::Reader<S>, &mut fastq::io::Writer<X>),
-    rng: &mut Rng,
-    p: f64,
+    is_kept: &mut F,
 ) -> Result<(u64, u64), SubsampleError>
 where
     R: BufRead,
     S: BufRead,
     W: Write,
     X: Write,
-    Rng: rand::Rng,
+    F: FnMut(&Record) -> bool,
 {
     let mut s1 = Record::default();
     let mut s2 = Record::default();
@@ -168,9 +383,7 @@ where
             (0, len) if len > 0 => return Err(SubsampleError::UnexpectedEof("r1-src")),
             (len, 0) if len > 0 => return Err(SubsampleError::UnexpectedEof("r2-src")),
             (_, _) => {
-                let q: f64 = rng.random();
-
-                if q <= p {
+                if is_kept(&s1) {
                     w1.write_record(&s1)?;
                     w2.write_record(&s2)?;
                     n += 1;
@@ -184,11 +397,39 @@ where
     Ok((n, total))
 }
 
+fn ensure_single_stdio<'a, I, J>(srcs: I, dsts: J) -> Result<(), SubsampleError>
+where
+    I: IntoIterator<Item = &'a PathBuf>,
+    J: IntoIterator<Item = &'a PathBuf>,
+{
+    if fastq::fs::has_multiple_stdio(srcs) {
+        Err(SubsampleError::MultipleStdinSources)
+    } else if fastq::fs::has_multiple_stdio(dsts) {
+        Err(SubsampleError::MultipleStdoutDestinations)
+    } else {
+        Ok(())
+    }
+}
+
+// Modes that count records or bases before sampling read the sources twice, which is not
+// possible with stdin.
+fn ensure_rereadable(r1_src: &Path, r2_src: Option<&Path>) -> Result<(), SubsampleError> {
+    if fastq::fs::is_stdio(r1_src) {
+        Err(SubsampleError::StdinSource("r1-src"))
+    } else if r2_src.is_some_and(fastq::fs::is_stdio) {
+        Err(SubsampleError::StdinSource("r2-src"))
+    } else {
+        Ok(())
+    }
+}
+
 fn subsample_exact<Rng>(
     (r1_src, r1_dst): (&Path, &Path),
     (r2_src, r2_dst): (Option<&Path>, Option<&Path>),
     rng: Rng,
     mut record_count: u64,
+    read_options: ReadOptions,
+    write_options: WriteOptions,
 ) -> Result<(), SubsampleError>
 where
     Rng: rand::Rng,
@@ -196,9 +437,12 @@ where
     let span = info_span!("subsample_exact", record_count = record_count);
     let _span_ctx = span.enter();
 
+    ensure_rereadable(r1_src, r2_src)?;
+    let r2 = r2_paths(r2_src, r2_dst)?;
+
     info!("counting records");
 
-    let line_count = count_lines(r1_src)?;
+    let line_count = count_lines(r1_src, read_options)?;
     let actual_record_count = line_count / 4;
 
     info!(actual_record_count = actual_record_count, "counted records");
@@ -219,45 +463,25 @@ where
     let bitmap = build_filter(rng, actual_record_count, record_count)?;
     info!("built filter");
 
-    let mut r1 = fastq::fs::open(r1_src).map_err(|e| SubsampleError::OpenFile(e, r1_src.into()))?;
-    let mut w1 =
-        fastq::fs::create(r1_dst).map_err(|e| SubsampleError::CreateFile(e, r1_dst.into()))?;
+    sample_by_filter((r1_src, r1_dst), r2, &bitmap, read_options, write_options)?;
 
-    match (r2_src, r2_dst) {
-        (Some(r2_src), Some(r2_dst)) => {
-            info!("sampling paired end reads");
-
-            let mut r2 =
-                fastq::fs::open(r2_src).map_err(|e| SubsampleError::OpenFile(e, r2_src.into()))?;
-            let mut w2 = fastq::fs::create(r2_dst)
-                .map_err(|e| SubsampleError::CreateFile(e, r2_dst.into()))?;
-
-            subsample_exact_paired((&mut r1, &mut w1), (&mut r2, &mut w2), &bitmap)?;
-        }
-        (Some(_), None) => return Err(SubsampleError::MissingDestination("r2-dst")),
-        (None, Some(_)) => return Err(SubsampleError::MissingSource("r2-src")),
-        (None, None) => {
-            info!("sampling single end reads");
-            subsample_exact_single(&mut r1, &mut w1, &bitmap)?;
-        }
-    }
-
-    let percentage = (record_count as f64) / (actual_record_count as f64) * 100.0;
     info!(
         "sampled {}/{} ({:.1}%) records",
-        record_count, actual_record_count, percentage
+        record_count,
+        n,
+        percentage(record_count, n)
     );
 
     Ok(())
 }
 
-fn count_lines<P>(src: P) -> io::Result<usize>
+fn count_lines<P>(src: P, options: ReadOptions) -> io::Result<usize>
 where
     P: AsRef<Path>,
 {
     const LINE_FEED: u8 = b'\n';
 
-    let mut reader = open(src)?;
+    let mut reader = fastq::fs::open_decompressed(src, options)?;
     let mut n = 0;
 
     loop {
@@ -276,23 +500,6 @@ where
     Ok(n)
 }
 
-fn open<P>(src: P) -> io::Result<Box<dyn BufRead>>
-where
-    P: AsRef<Path>,
-{
-    let path = src.as_ref();
-    let extension = path.extension();
-    let reader = File::open(path).map(BufReader::new)?;
-
-    match extension.and_then(|ext| ext.to_str()) {
-        Some("gz") => {
-            let decoder = MultiGzDecoder::new(reader);
-            Ok(Box::new(BufReader::new(decoder)))
-        }
-        _ => Ok(Box::new(reader)),
-    }
-}
-
 fn build_filter<Rng>(
     mut rng: Rng,
     src_record_count: usize,
@@ -379,6 +586,397 @@ where
     Ok(())
 }
 
+fn subsample_bases<Rng>(
+    (r1_src, r1_dst): (&Path, &Path),
+    (r2_src, r2_dst): (Option<&Path>, Option<&Path>),
+    rng: Rng,
+    mut target_bases: u64,
+    read_options: ReadOptions,
+    write_options: WriteOptions,
+) -> Result<(), SubsampleError>
+where
+    Rng: rand::Rng,
+{
+    let span = info_span!("subsample_bases", target_bases = target_bases);
+    let _span_ctx = span.enter();
+
+    ensure_rereadable(r1_src, r2_src)?;
+
+    let r2 = r2_paths(r2_src, r2_dst)?;
+
+    let mut srcs = vec![r1_src];
+
+    if let Some((r2_src, _)) = r2 {
+        srcs.push(r2_src);
+    }
+
+    info!("counting bases");
+
+    let mut readers = srcs
+        .iter()
+        .map(|&src| open(src, read_options))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let base_counts = count_bases(&mut readers)?;
+    let total_bases: u64 = base_counts.iter().map(|&n| u64::from(n)).sum();
+
+    info!(
+        record_count = base_counts.len(),
+        base_count = total_bases,
+        "counted bases"
+    );
+
+    if target_bases > total_bases {
+        warn!(
+            "target bases ({}) > source base count ({}). Using target-bases = {} instead.",
+            target_bases, total_bases, total_bases
+        );
+
+        target_bases = total_bases;
+    }
+
+    info!("building filter");
+    let (bitmap, bases) = build_bases_filter(rng, &base_counts, target_bases)?;
+    info!("built filter");
+
+    sample_by_filter((r1_src, r1_dst), r2, &bitmap, read_options, write_options)?;
+
+    let n = bitmap.count_ones();
+    let total = base_counts.len();
+
+    info!(
+        "sampled {}/{} records, {}/{} ({:.1}%) bases",
+        n,
+        total,
+        bases,
+        total_bases,
+        percentage(bases, total_bases)
+    );
+
+    Ok(())
+}
+
+/// Samples whole records (or pairs) with probability `target_bases` / total bases.
+///
+/// Unlike [`subsample_bases`], this only counts the total number of bases in the first pass, so it
+/// uses constant memory, but the number of bases kept is only close to the target.
+fn subsample_bases_approximate<Rng>(
+    (r1_src, r1_dst): (&Path, &Path),
+    (r2_src, r2_dst): (Option<&Path>, Option<&Path>),
+    mut rng: Rng,
+    target_bases: u64,
+    read_options: ReadOptions,
+    write_options: WriteOptions,
+) -> Result<(), SubsampleError>
+where
+    Rng: rand::Rng,
+{
+    let span = info_span!("subsample_bases_approximate", target_bases = target_bases);
+    let _span_ctx = span.enter();
+
+    ensure_rereadable(r1_src, r2_src)?;
+
+    let r2 = r2_paths(r2_src, r2_dst)?;
+
+    let mut srcs = vec![r1_src];
+
+    if let Some((r2_src, _)) = r2 {
+        srcs.push(r2_src);
+    }
+
+    info!("counting bases");
+
+    let mut readers = srcs
+        .iter()
+        .map(|&src| open(src, read_options))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let total_bases = count_total_bases(&mut readers)?;
+
+    info!(base_count = total_bases, "counted bases");
+
+    let probability = if total_bases == 0 {
+        0.0
+    } else {
+        ((target_bases as f64) / (total_bases as f64)).min(1.0)
+    };
+
+    info!(probability = probability, "calculated probability");
+
+    let mut is_kept = |_: &Record| rng.random::<f64>() < probability;
+
+    let (n, total) = sample_by_predicate(
+        (r1_src, r1_dst),
+        r2,
+        &mut is_kept,
+        read_options,
+        write_options,
+    )?;
+
+    info!(
+        "sampled {}/{} ({:.1}%) records",
+        n,
+        total,
+        percentage(n, total)
+    );
+
+    Ok(())
+}
+
+// Returns the total number of bases in all sets of records.
+fn count_total_bases<R>(readers: &mut [fastq::io::Reader<R>]) -> Result<u64, SubsampleError>
+where
+    R: BufRead,
+{
+    let mut total_bases = 0;
+    let mut records = vec![Record::default(); readers.len()];
+
+    while read_records(readers, &mut records)? {
+        total_bases += records
+            .iter()
+            .map(|record| record.sequence().len() as u64)
+            .sum::<u64>();
+    }
+
+    Ok(total_bases)
+}
+
+// Returns the number of bases in each set of records.
+fn count_bases<R>(readers: &mut [fastq::io::Reader<R>]) -> Result<Vec<u32>, SubsampleError>
+where
+    R: BufRead,
+{
+    let mut base_counts = Vec::new();
+    let mut records = vec![Record::default(); readers.len()];
+
+    while read_records(readers, &mut records)? {
+        let n: usize = records.iter().map(|record| record.sequence().len()).sum();
+        let n = u32::try_from(n).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        base_counts.push(n);
+    }
+
+    Ok(base_counts)
+}
+
+/// Randomly selects sets of records until their total number of bases is at least
+/// `target_bases`.
+///
+/// This returns the selected sets and their total number of bases, which overshoots the target
+/// by less than the size of the last selected set.
+fn build_bases_filter<Rng>(
+    mut rng: Rng,
+    base_counts: &[u32],
+    target_bases: u64,
+) -> Result<(BitVec, u64), SubsampleError>
+where
+    Rng: rand::Rng,
+{
+    let mut bitmap = BitVec::new();
+    bitmap.resize(base_counts.len(), false);
+
+    let total_bases: u64 = base_counts.iter().map(|&n| u64::from(n)).sum();
+
+    if target_bases == 0 {
+        return Ok((bitmap, 0));
+    } else if target_bases >= total_bases {
+        bitmap.fill(true);
+        return Ok((bitmap, total_bases));
+    }
+
+    let distribution =
+        Uniform::new(0, base_counts.len()).map_err(SubsampleError::InvalidUniformRange)?;
+
+    let mut bases = 0;
+
+    while bases < target_bases {
+        let i = distribution.sample(&mut rng);
+
+        if !bitmap[i] {
+            bitmap.set(i, true);
+            bases += u64::from(base_counts[i]);
+        }
+    }
+
+    Ok((bitmap, bases))
+}
+
+fn subsample_reservoir<Rng>(
+    (r1_src, r1_dst): (&Path, &Path),
+    (r2_src, r2_dst): (Option<&Path>, Option<&Path>),
+    mut rng: Rng,
+    record_count: u64,
+    read_options: ReadOptions,
+    write_options: WriteOptions,
+) -> Result<(), SubsampleError>
+where
+    Rng: rand::Rng,
+{
+    let span = info_span!("subsample_reservoir", record_count = record_count);
+    let _span_ctx = span.enter();
+
+    let mut srcs = vec![r1_src];
+    let mut dsts = vec![r1_dst];
+
+    if let Some((r2_src, r2_dst)) = r2_paths(r2_src, r2_dst)? {
+        info!("sampling paired end reads");
+        srcs.push(r2_src);
+        dsts.push(r2_dst);
+    } else {
+        info!("sampling single end reads");
+    }
+
+    let mut readers = srcs
+        .iter()
+        .map(|&src| open(src, read_options))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut writers = dsts
+        .iter()
+        .map(|&dst| create(dst, write_options))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (reservoir, total) = build_reservoir(&mut readers, &mut rng, record_count)?;
+
+    for (_, records) in &reservoir {
+        for (writer, record) in writers.iter_mut().zip(records) {
+            writer.write_record(record)?;
+        }
+    }
+
+    let n = reservoir.len() as u64;
+
+    info!(
+        "sampled {}/{} ({:.1}%) records",
+        n,
+        total,
+        percentage(n, total)
+    );
+
+    Ok(())
+}
+
+// A list of sampled sets of records with their record indices.
+type Reservoir = Vec<(u64, Vec<Record>)>;
+
+/// Samples `k` sets of records uniformly at random in a single pass.
+///
+/// This uses Algorithm L. See Li, K.-H. (1994). "Reservoir-Sampling Algorithms of Time
+/// Complexity O(n(1 + log(N/n)))". ACM Transactions on Mathematical Software, 20(4), 481–493.
+///
+/// This returns the sampled sets with their record indices, sorted by index, and the total number
+/// of sets read.
+fn build_reservoir<R, Rng>(
+    readers: &mut [fastq::io::Reader<R>],
+    rng: &mut Rng,
+    k: u64,
+) -> Result<(Reservoir, u64), SubsampleError>
+where
+    R: BufRead,
+    Rng: rand::Rng,
+{
+    let mut reservoir = Vec::new();
+    let mut records = vec![Record::default(); readers.len()];
+
+    let mut w = 1.0;
+    let mut next_i = u64::MAX;
+
+    let mut i = 0;
+
+    while read_records(readers, &mut records)? {
+        if i < k {
+            reservoir.push((i, copy_records(&records)));
+
+            if i + 1 == k {
+                w *= next_weight(rng, k);
+                next_i = next_index(rng, i, w);
+            }
+        } else if i == next_i {
+            let j = rng.random_range(0..reservoir.len());
+            reservoir[j] = (i, copy_records(&records));
+
+            w *= next_weight(rng, k);
+            next_i = next_index(rng, i, w);
+        }
+
+        i += 1;
+    }
+
+    reservoir.sort_unstable_by_key(|(i, _)| *i);
+
+    Ok((reservoir, i))
+}
+
+// Returns a sample of (0.0, 1.0].
+fn random_unit<Rng>(rng: &mut Rng) -> f64
+where
+    Rng: rand::Rng,
+{
+    1.0 - rng.random::<f64>()
+}
+
+fn next_weight<Rng>(rng: &mut Rng, k: u64) -> f64
+where
+    Rng: rand::Rng,
+{
+    (random_unit(rng).ln() / (k as f64)).exp()
+}
+
+fn next_skip<Rng>(rng: &mut Rng, w: f64) -> u64
+where
+    Rng: rand::Rng,
+{
+    (random_unit(rng).ln() / (-w).ln_1p()).floor() as u64
+}
+
+// Returns the index of the next record to replace in the reservoir.
+//
+// When `w` is tiny, the skip can be huge. The sum saturates at `u64::MAX`, which means no more
+// replacements.
+fn next_index<Rng>(rng: &mut Rng, i: u64, w: f64) -> u64
+where
+    Rng: rand::Rng,
+{
+    i.saturating_add(next_skip(rng, w)).saturating_add(1)
+}
+
+// Reads a record from each reader. This returns `false` if all readers are at EOF.
+fn read_records<R>(
+    readers: &mut [fastq::io::Reader<R>],
+    records: &mut [Record],
+) -> Result<bool, SubsampleError>
+where
+    R: BufRead,
+{
+    const SRC_NAMES: [&str; 2] = ["r1-src", "r2-src"];
+
+    let mut eof_count = 0;
+    let mut first_eof_i = None;
+
+    for (i, (reader, record)) in readers.iter_mut().zip(records.iter_mut()).enumerate() {
+        if reader.read_record(record)? == 0 {
+            eof_count += 1;
+            first_eof_i.get_or_insert(i);
+        }
+    }
+
+    match first_eof_i {
+        None => Ok(true),
+        Some(_) if eof_count == readers.len() => Ok(false),
+        Some(i) => Err(SubsampleError::UnexpectedEof(SRC_NAMES[i])),
+    }
+}
+
+// Copies records out of the reader buffer so that holding them does not keep the buffer alive.
+fn copy_records(records: &[Record]) -> Vec<Record> {
+    records
+        .iter()
+        .map(|record| Record {
+            buf: Bytes::copy_from_slice(&record.buf),
+            ..record.clone()
+        })
+        .collect()
+}
+
 #[derive(Debug, Error)]
 pub enum SubsampleError {
     #[error("I/O error")]
@@ -395,6 +993,16 @@ pub enum SubsampleError {
     InvalidProbability(f64),
     #[error("{0} unexpectedly ended")]
     UnexpectedEof(&'static str),
+    #[error("invalid destination count: expected {expected}, got {actual}")]
+    InvalidDestinationCount { expected: usize, actual: usize },
+    #[error("invalid target coverage: expected > 0.0, got {0}")]
+    InvalidTargetCoverage(f64),
+    #[error("{0} cannot be read from stdin in this mode: use `--reservoir` or `--probability`")]
+    StdinSource(&'static str),
+    #[error("stdin (`-`) can only be used as one source")]
+    MultipleStdinSources,
+    #[error("stdout (`-`) can only be used as one destination")]
+    MultipleStdoutDestinations,
     #[error("invalid uniform range")]
     InvalidUniformRange(rand::distr::uniform::Error),
 }
@@ -416,7 +1024,9 @@ mod tests {
 
         let mut rng = SmallRng::seed_from_u64(0);
 
-        subsample_single(&mut reader, &mut writer, &mut rng, 0.33)?;
+        subsample_single(&mut reader, &mut writer, &mut |_| {
+            rng.random::<f64>() <= 0.33
+        })?;
 
         let expected = b"@r1\nACGT\n+\nFQLB\n@r4\nACGT\n+\nFQLB\n";
         assert_eq!(writer.get_ref(), expected);
@@ -445,7 +1055,9 @@ mod tests {
 
         let mut rng = SmallRng::seed_from_u64(0);
 
-        subsample_paired((&mut r1, &mut w1), (&mut r2, &mut w2), &mut rng, 0.33)?;
+        subsample_paired((&mut r1, &mut w1), (&mut r2, &mut w2), &mut |_| {
+            rng.random::<f64>() <= 0.33
+        })?;
 
         let w1_expected = b"@r1\nACGT\n+\nFQLB\n@r4\nACGT\n+\nFQLB\n";
         assert_eq!(w1.get_ref(), w1_expected);
@@ -456,6 +1068,116 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_name_hash_sampler() {
+        let sampler = NameHashSampler::new(0, 0.5);
+
+        let kept: Vec<_> = (0..1000)
+            .map(|i| {
+                let r1 = Record::new(format!("@r{i}/1"), "ACGT", "+", "FQLB");
+                let r2 = Record::new(format!("@r{i} 2:N:0:1"), "TGCA", "+", "BLQF");
+
+                let is_kept = sampler.is_kept(&r1);
+                assert_eq!(sampler.is_kept(&r2), is_kept);
+
+                is_kept
+            })
+            .collect();
+
+        let n = kept.iter().filter(|&&is_kept| is_kept).count();
+        assert!((400..600).contains(&n));
+
+        let other_sampler = NameHashSampler::new(0, 0.5);
+        let record = Record::new("@r0/1", "ACGT", "+", "FQLB");
+        assert_eq!(other_sampler.is_kept(&record), kept[0]);
+
+        assert!(!NameHashSampler::new(0, 0.0).is_kept(&record));
+    }
+
+    #[test]
+    fn test_percentage() {
+        assert_eq!(percentage(0, 0), 0.0);
+        assert_eq!(percentage(0, 4), 0.0);
+        assert_eq!(percentage(1, 4), 25.0);
+        assert_eq!(percentage(4, 4), 100.0);
+    }
+
+    #[test]
+    fn test_ensure_single_stdio() {
+        let r1 = PathBuf::from("r1.fq");
+        let stdio = PathBuf::from("-");
+
+        assert!(ensure_single_stdio([&stdio], [&stdio]).is_ok());
+        assert!(ensure_single_stdio([&stdio, &r1], [&r1, &stdio]).is_ok());
+
+        assert!(matches!(
+            ensure_single_stdio([&stdio, &stdio], [&r1]),
+            Err(SubsampleError::MultipleStdinSources)
+        ));
+        assert!(matches!(
+            ensure_single_stdio([&r1], [&stdio, &stdio]),
+            Err(SubsampleError::MultipleStdoutDestinations)
+        ));
+    }
+
+    #[test]
+    fn test_count_bases() -> Result<(), SubsampleError> {
+        let r1_data = b"@r1\nACGT\n+\nFQLB\n@r2\nAC\n+\nFQ\n";
+        let r2_data = b"@r1\nTGC\n+\nBLQ\n@r2\nT\n+\nB\n";
+
+        let mut readers = [
+            fastq::io::Reader::new(&r1_data[..]),
+            fastq::io::Reader::new(&r2_data[..]),
+        ];
+
+        assert_eq!(count_bases(&mut readers)?, [7, 3]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_total_bases() -> Result<(), SubsampleError> {
+        let r1_data = b"@r1\nACGT\n+\nFQLB\n@r2\nAC\n+\nFQ\n";
+        let r2_data = b"@r1\nTGC\n+\nBLQ\n@r2\nT\n+\nB\n";
+
+        let mut readers = [
+            fastq::io::Reader::new(&r1_data[..]),
+            fastq::io::Reader::new(&r2_data[..]),
+        ];
+
+        assert_eq!(count_total_bases(&mut readers)?, 10);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_bases_filter() -> Result<(), SubsampleError> {
+        let base_counts = [100, 200, 50, 300, 150, 100, 250, 50];
+
+        for target_bases in [1, 400, 800, 1199] {
+            let rng = SmallRng::seed_from_u64(0);
+            let (bitmap, bases) = build_bases_filter(rng, &base_counts, target_bases)?;
+
+            let actual_bases: u64 = bitmap.iter_ones().map(|i| u64::from(base_counts[i])).sum();
+            assert_eq!(bases, actual_bases);
+
+            // The overshoot is less than the largest set.
+            assert!((target_bases..target_bases + 300).contains(&bases));
+        }
+
+        let rng = SmallRng::seed_from_u64(0);
+        let (bitmap, bases) = build_bases_filter(rng, &base_counts, 1200)?;
+        assert!(bitmap.all());
+        assert_eq!(bases, 1200);
+
+        let rng = SmallRng::seed_from_u64(0);
+        let (bitmap, bases) = build_bases_filter(rng, &base_counts, 0)?;
+        assert!(bitmap.not_any());
+        assert_eq!(bases, 0);
+
+        Ok(())
+    }
+
     #[test]
     fn test_subsample_exact_single() -> Result<(), SubsampleError> {
         let data = b"@r1\nACGT\n+\nFQLB
@@ -477,6 +1199,93 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_build_reservoir() -> Result<(), SubsampleError> {
+        let r1_data = b"@r1\nACGT\n+\nFQLB
+@r2\nACGT\n+\nFQLB
+@r3\nACGT\n+\nFQLB
+@r4\nACGT\n+\nFQLB
+@r5\nACGT\n+\nFQLB
+";
+
+        let r2_data = b"@r1\nTGCA\n+\nBLQF
+@r2\nTGCA\n+\nBLQF
+@r3\nTGCA\n+\nBLQF
+@r4\nTGCA\n+\nBLQF
+@r5\nTGCA\n+\nBLQF
+";
+
+        let mut readers = [
+            fastq::io::Reader::new(&r1_data[..]),
+            fastq::io::Reader::new(&r2_data[..]),
+        ];
+
+        let mut rng = SmallRng::seed_from_u64(0);
+        let (reservoir, total) = build_reservoir(&mut readers, &mut rng, 3)?;
+
+        assert_eq!(total, 5);
+        assert_eq!(reservoir.len(), 3);
+        assert!(reservoir.windows(2).all(|w| w[0].0 < w[1].0));
+
+        for (i, records) in &reservoir {
+            let name = format!("@r{}", i + 1);
+            assert_eq!(records[0].name(), name.as_bytes());
+            assert_eq!(records[1].name(), name.as_bytes());
+        }
+
+        let mut readers = [fastq::io::Reader::new(&r1_data[..])];
+        let (reservoir, total) = build_reservoir(&mut readers, &mut rng, 8)?;
+        assert_eq!(total, 5);
+        assert_eq!(reservoir.len(), 5);
+
+        let mut readers = [
+            fastq::io::Reader::new(&r1_data[..]),
+            fastq::io::Reader::new(&r2_data[..32]),
+        ];
+
+        assert!(matches!(
+            build_reservoir(&mut readers, &mut rng, 3),
+            Err(SubsampleError::UnexpectedEof("r2-src"))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_next_index() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        assert_eq!(
+            next_index(&mut rng, u64::MAX - 1, 1.0 - f64::EPSILON),
+            u64::MAX
+        );
+        assert_eq!(next_index(&mut rng, 8, f64::MIN_POSITIVE), u64::MAX);
+    }
+
+    #[test]
+    fn test_build_reservoir_is_uniform() -> Result<(), SubsampleError> {
+        let data = b"@r\nA\n+\nF\n".repeat(10);
+
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut counts = [0; 10];
+
+        for _ in 0..10000 {
+            let mut readers = [fastq::io::Reader::new(&data[..])];
+            let (reservoir, _) = build_reservoir(&mut readers, &mut rng, 2)?;
+
+            for (i, _) in reservoir {
+                counts[i as usize] += 1;
+            }
+        }
+
+        // Each record is expected to be sampled 2000 times.
+        assert!(
+            counts.iter().all(|&n| (1800..2200).contains(&n)),
+            "{counts:?}"
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_subsample_exact_paired() -> Result<(), SubsampleError> {
         let r1_data = b"@r1\nACGT\n+\nFQLB
@@ -0,0 +1,388 @@
+//! Multiple subsample replicates from a single pass.
+
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    io::{BufRead, Write},
+    ops::RangeBounds,
+    rc::Rc,
+};
+
+use rand::Rng;
+use tracing::{info, info_span};
+
+use super::{
+    NameHashSampler, SubsampleError, VALID_PROBABILITY_RANGE, build_rng, copy_records, create,
+    open, percentage, read_records,
+};
+use crate::{
+    cli::SubsampleArgs,
+    fastq::{
+        self, Record,
+        fs::{ReadOptions, WriteOptions},
+    },
+};
+
+pub(super) fn subsample_replicates(args: &SubsampleArgs) -> Result<(), SubsampleError> {
+    let replicate_count = args.probability.len().max(args.record_count.len());
+
+    let span = info_span!(
+        "subsample_replicates",
+        replicate_count = replicate_count,
+        nested = args.nested
+    );
+    let _span_ctx = span.enter();
+
+    let mut srcs = vec![args.r1_src.as_path()];
+    let mut dsts = vec![args.r1_dst.as_slice()];
+
+    match (args.r2_src.as_ref(), args.r2_dst.is_empty()) {
+        (Some(r2_src), false) => {
+            info!("sampling paired end reads");
+            srcs.push(r2_src);
+            dsts.push(&args.r2_dst);
+        }
+        (Some(_), true) => return Err(SubsampleError::MissingDestination("r2-dst")),
+        (None, false) => return Err(SubsampleError::MissingSource("r2-src")),
+        (None, true) => info!("sampling single end reads"),
+    }
+
+    if let Some(replicate_dsts) = dsts.iter().find(|d| d.len() != replicate_count) {
+        return Err(SubsampleError::InvalidDestinationCount {
+            expected: replicate_count,
+            actual: replicate_dsts.len(),
+        });
+    }
+
+    if let Some(&probability) = args
+        .probability
+        .iter()
+        .find(|p| !VALID_PROBABILITY_RANGE.contains(*p))
+    {
+        return Err(SubsampleError::InvalidProbability(probability));
+    }
+
+    let read_options = ReadOptions {
+        threads: args.threads,
+    };
+
+    let mut readers = srcs
+        .iter()
+        .map(|&src| open(src, read_options))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let write_options = WriteOptions {
+        compression: args.output_compression,
+        compression_level: args.compression_level,
+        threads: args.threads,
+    };
+
+    let mut writers = (0..replicate_count)
+        .map(|i| {
+            dsts.iter()
+                .map(|replicate_dsts| create(&replicate_dsts[i], write_options))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (counts, total) = if args.probability.is_empty() {
+        sample_by_record_count(
+            &mut readers,
+            &mut writers,
+            build_rng(args.seed),
+            &args.record_count,
+            args.nested,
+        )?
+    } else if args.hash_by_name {
+        let seed = args.seed.unwrap_or_default();
+        info!(seed = seed, "sampling by name hash");
+
+        // Independent replicates use a different seed for each replicate.
+        let samplers: Vec<_> = args
+            .probability
+            .iter()
+            .enumerate()
+            .map(|(i, &probability)| {
+                let seed = if args.nested {
+                    seed
+                } else {
+                    seed.wrapping_add(i as u64)
+                };
+
+                NameHashSampler::new(seed, probability)
+            })
+            .collect();
+
+        sample_by_probability(&mut readers, &mut writers, |records, is_kept| {
+            for (is_kept, sampler) in is_kept.iter_mut().zip(&samplers) {
+                *is_kept = sampler.is_kept(&records[0]);
+            }
+        })?
+    } else {
+        let mut rng = build_rng(args.seed);
+        let probabilities = &args.probability;
+        let is_nested = args.nested;
+
+        sample_by_probability(&mut readers, &mut writers, |_, is_kept| {
+            let q: f64 = rng.random();
+
+            for (is_kept, &p) in is_kept.iter_mut().zip(probabilities) {
+                let q = if is_nested { q } else { rng.random() };
+                *is_kept = q <= p;
+            }
+        })?
+    };
+
+    for (i, n) in counts.into_iter().enumerate() {
+        info!(
+            "replicate {}: sampled {}/{} ({:.1}%) records",
+            i,
+            n,
+            total,
+            percentage(n, total)
+        );
+    }
+
+    Ok(())
+}
+
+/// Samples each set of records into the replicates chosen by `f`.
+///
+/// `f` is given the set of records and a list of flags, one for each replicate, to set whether
+/// the set is kept in that replicate.
+///
+/// This returns the number of sets kept for each replicate and the total number of sets read.
+fn sample_by_probability<R, W, F>(
+    readers: &mut [fastq::io::Reader<R>],
+    writers: &mut [Vec<fastq::io::Writer<W>>],
+    mut f: F,
+) -> Result<(Vec<u64>, u64), SubsampleError>
+where
+    R: BufRead,
+    W: Write,
+    F: FnMut(&[Record], &mut [bool]),
+{
+    let mut records = vec![Record::default(); readers.len()];
+    let mut is_kept = vec![false; writers.len()];
+    let mut counts = vec![0; writers.len()];
+    let mut total = 0;
+
+    while read_records(readers, &mut records)? {
+        f(&records, &mut is_kept);
+
+        for ((replicate_writers, &is_kept), n) in writers.iter_mut().zip(&is_kept).zip(&mut counts)
+        {
+            if is_kept {
+                for (writer, record) in replicate_writers.iter_mut().zip(&records) {
+                    writer.write_record(record)?;
+                }
+
+                *n += 1;
+            }
+        }
+
+        total += 1;
+    }
+
+    Ok((counts, total))
+}
+
+/// Samples an exact number of sets of records for each replicate.
+///
+/// Each set is assigned a random key, and each replicate keeps the sets with the smallest keys
+/// (bottom-k sampling). Nested replicates share the same keys. Sampled sets are held in memory,
+/// shared between replicates, until all sources are read.
+///
+/// This returns the number of sets kept for each replicate and the total number of sets read.
+fn sample_by_record_count<R, W, Rng>(
+    readers: &mut [fastq::io::Reader<R>],
+    writers: &mut [Vec<fastq::io::Writer<W>>],
+    mut rng: Rng,
+    record_counts: &[u64],
+    is_nested: bool,
+) -> Result<(Vec<u64>, u64), SubsampleError>
+where
+    R: BufRead,
+    W: Write,
+    Rng: rand::Rng,
+{
+    let mut heaps = vec![BinaryHeap::new(); record_counts.len()];
+    let mut keys = vec![0; record_counts.len()];
+    let mut records = vec![Record::default(); readers.len()];
+    let mut total = 0;
+
+    while read_records(readers, &mut records)? {
+        if is_nested {
+            keys.fill(rng.random());
+        } else {
+            keys.iter_mut().for_each(|key| *key = rng.random());
+        }
+
+        let mut sampled_records: Option<Rc<[Record]>> = None;
+
+        for ((heap, &k), &key) in heaps.iter_mut().zip(record_counts).zip(&keys) {
+            let is_full = heap.len() as u64 >= k;
+
+            if is_full && heap.peek().is_none_or(|entry: &Entry| key >= entry.key) {
+                continue;
+            }
+
+            if is_full {
+                heap.pop();
+            }
+
+            let records = sampled_records
+                .get_or_insert_with(|| copy_records(&records).into())
+                .clone();
+
+            heap.push(Entry {
+                key,
+                index: total,
+                records,
+            });
+        }
+
+        total += 1;
+    }
+
+    let mut counts = Vec::with_capacity(heaps.len());
+
+    for (heap, replicate_writers) in heaps.into_iter().zip(writers) {
+        let mut entries = heap.into_vec();
+        entries.sort_unstable_by_key(|entry| entry.index);
+
+        for entry in &entries {
+            for (writer, record) in replicate_writers.iter_mut().zip(entry.records.iter()) {
+                writer.write_record(record)?;
+            }
+        }
+
+        counts.push(entries.len() as u64);
+    }
+
+    Ok((counts, total))
+}
+
+// A sampled set of records, ordered by key.
+#[derive(Clone)]
+struct Entry {
+    key: u64,
+    index: u64,
+    records: Rc<[Record]>,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.key, self.index).cmp(&(other.key, other.index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::SmallRng};
+
+    use super::*;
+
+    fn build_data(n: usize) -> Vec<u8> {
+        (0..n)
+            .flat_map(|i| format!("@r{i}\nACGT\n+\nFQLB\n").into_bytes())
+            .collect()
+    }
+
+    fn build_writers(n: usize) -> Vec<Vec<fastq::io::Writer<Vec<u8>>>> {
+        (0..n)
+            .map(|_| vec![fastq::io::Writer::new(Vec::new())])
+            .collect()
+    }
+
+    fn names(writer: &fastq::io::Writer<Vec<u8>>) -> Vec<String> {
+        String::from_utf8_lossy(writer.get_ref())
+            .lines()
+            .step_by(4)
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn test_sample_by_probability() -> Result<(), SubsampleError> {
+        let data = build_data(100);
+        let mut readers = [fastq::io::Reader::new(&data[..])];
+        let mut writers = build_writers(2);
+
+        let mut i = 0;
+
+        let (counts, total) = sample_by_probability(&mut readers, &mut writers, |_, is_kept| {
+            is_kept[0] = i % 2 == 0;
+            is_kept[1] = i % 4 == 0;
+            i += 1;
+        })?;
+
+        assert_eq!(counts, [50, 25]);
+        assert_eq!(total, 100);
+        assert_eq!(names(&writers[1][0])[..2], ["@r0", "@r4"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sample_by_record_count() -> Result<(), SubsampleError> {
+        let data = build_data(100);
+
+        let mut readers = [fastq::io::Reader::new(&data[..])];
+        let mut writers = build_writers(3);
+        let rng = SmallRng::seed_from_u64(0);
+
+        let (counts, total) =
+            sample_by_record_count(&mut readers, &mut writers, rng, &[10, 30, 200], true)?;
+
+        assert_eq!(counts, [10, 30, 100]);
+        assert_eq!(total, 100);
+
+        let small = names(&writers[0][0]);
+        let large = names(&writers[1][0]);
+        assert!(small.iter().all(|name| large.contains(name)));
+
+        // Records are written in input order.
+        let indices: Vec<usize> = large
+            .iter()
+            .map(|name| name[2..].parse().unwrap())
+            .collect();
+        assert!(indices.is_sorted());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sample_by_record_count_with_independent_replicates() -> Result<(), SubsampleError> {
+        let data = build_data(100);
+
+        let mut readers = [fastq::io::Reader::new(&data[..])];
+        let mut writers = build_writers(2);
+        let rng = SmallRng::seed_from_u64(0);
+
+        let (counts, _) =
+            sample_by_record_count(&mut readers, &mut writers, rng, &[10, 30], false)?;
+
+        assert_eq!(counts, [10, 30]);
+
+        let small = names(&writers[0][0]);
+        let large = names(&writers[1][0]);
+        assert!(!small.iter().all(|name| large.contains(name)));
+
+        Ok(())
+    }
+}
@@ -1,44 +1,252 @@
+pub mod bgzf;
+mod compression;
+
 use std::{
     fs::File,
-    io::{self, BufRead, BufReader, BufWriter, Write},
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    num::NonZeroUsize,
     path::Path,
 };
 
-use flate2::{Compression, bufread::MultiGzDecoder, write::GzEncoder};
-
+pub use self::compression::CompressionFormat;
 use super::io::{Reader, Writer};
 
-const GZ_EXTENSION: &str = "gz";
+/// The path that refers to stdin (when reading) or stdout (when writing).
+pub const STDIO_PATH: &str = "-";
+
+/// Returns whether the path refers to stdin or stdout, i.e., `-`.
+pub fn is_stdio<P>(path: P) -> bool
+where
+    P: AsRef<Path>,
+{
+    path.as_ref() == Path::new(STDIO_PATH)
+}
 
+/// Returns whether more than one of the paths refers to stdin or stdout, i.e., `-`.
+///
+/// Multiple readers of stdin would each take chunks of the same stream, and multiple writers to
+/// stdout would interleave their output.
+pub fn has_multiple_stdio<I, P>(paths: I) -> bool
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    paths.into_iter().filter(|path| is_stdio(path)).count() > 1
+}
+
+/// Options for creating a FASTQ writer.
+#[derive(Clone, Copy, Debug)]
+pub struct WriteOptions {
+    /// The compression format of the output.
+    ///
+    /// If `None`, the format is chosen from the extension of the destination (see
+    /// [`CompressionFormat::from_path`]), and stdout is written uncompressed.
+    pub compression: Option<CompressionFormat>,
+    /// The compression level. If `None`, the default level of the format is used.
+    pub compression_level: Option<u32>,
+    /// The number of threads used to compress BGZF output.
+    pub threads: NonZeroUsize,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            compression: None,
+            compression_level: None,
+            threads: NonZeroUsize::MIN,
+        }
+    }
+}
+
+/// Creates a FASTQ writer using the default options.
+///
+/// If `dst` is `-`, records are written to stdout.
 pub fn create<P>(dst: P) -> io::Result<Writer<Box<dyn Write>>>
+where
+    P: AsRef<Path>,
+{
+    create_with_options(dst, WriteOptions::default())
+}
+
+/// Creates a FASTQ writer.
+///
+/// If `dst` is `-`, records are written to stdout.
+pub fn create_with_options<P>(dst: P, options: WriteOptions) -> io::Result<Writer<Box<dyn Write>>>
+where
+    P: AsRef<Path>,
+{
+    create_compressed(dst, options).map(Writer::new)
+}
+
+/// Creates a destination as a compressed stream of bytes.
+///
+/// This returns an error with [`io::ErrorKind::Unsupported`] if the compression format is not
+/// enabled.
+pub fn create_compressed<P>(dst: P, options: WriteOptions) -> io::Result<Box<dyn Write>>
 where
     P: AsRef<Path>,
 {
     let path = dst.as_ref();
-    let writer = File::create(path).map(BufWriter::new)?;
+    let compression = options
+        .compression
+        .or_else(|| CompressionFormat::from_path(path));
 
-    match path.extension().and_then(|ext| ext.to_str()) {
-        Some(GZ_EXTENSION) => {
-            let level = Compression::default();
-            let encoder = GzEncoder::new(writer, level);
-            Ok(Writer::new(Box::new(encoder)))
+    if let Some(format) = compression {
+        format.validate(options.compression_level)?;
+    }
+
+    let writer: Box<dyn Write> = if is_stdio(path) {
+        Box::new(BufWriter::new(io::stdout().lock()))
+    } else {
+        Box::new(File::create(path).map(BufWriter::new)?)
+    };
+
+    match compression {
+        Some(format) => format.encoder(writer, options.compression_level, options.threads),
+        None => Ok(writer),
+    }
+}
+
+/// Options for opening a FASTQ reader.
+#[derive(Clone, Copy, Debug)]
+pub struct ReadOptions {
+    /// The number of threads used to decompress BGZF input.
+    ///
+    /// Other formats, including ordinary gzip, are always decompressed on the calling thread.
+    pub threads: NonZeroUsize,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            threads: NonZeroUsize::MIN,
         }
-        _ => Ok(Writer::new(Box::new(writer))),
     }
 }
 
+/// Opens a FASTQ reader using the default options.
+///
+/// If `src` is `-`, records are read from stdin. See [`open_decompressed`] for how compressed
+/// sources are handled.
 pub fn open<P>(src: P) -> io::Result<Reader<Box<dyn BufRead>>>
+where
+    P: AsRef<Path>,
+{
+    open_with_options(src, ReadOptions::default())
+}
+
+/// Opens a FASTQ reader.
+///
+/// If `src` is `-`, records are read from stdin. See [`open_decompressed`] for how compressed
+/// sources are handled.
+pub fn open_with_options<P>(src: P, options: ReadOptions) -> io::Result<Reader<Box<dyn BufRead>>>
+where
+    P: AsRef<Path>,
+{
+    open_decompressed(src, options).map(Reader::new)
+}
+
+/// Opens a source as a decompressed stream of bytes.
+///
+/// The compression format is detected from the content of the source rather than its extension.
+/// Sources that do not start with a known magic number are read as is. This returns an error
+/// with [`io::ErrorKind::Unsupported`] if the source is compressed using a format that is not
+/// enabled.
+pub fn open_decompressed<P>(src: P, options: ReadOptions) -> io::Result<Box<dyn BufRead>>
 where
     P: AsRef<Path>,
 {
     let path = src.as_ref();
-    let reader = File::open(path).map(BufReader::new)?;
 
-    match path.extension().and_then(|ext| ext.to_str()) {
-        Some(GZ_EXTENSION) => {
-            let decoder = MultiGzDecoder::new(reader);
-            Ok(Reader::new(Box::new(BufReader::new(decoder))))
+    if is_stdio(path) {
+        decompress(BufReader::new(io::stdin().lock()), options)
+    } else {
+        let reader = File::open(path).map(BufReader::new)?;
+        decompress(reader, options)
+    }
+}
+
+fn decompress<R>(mut reader: R, options: ReadOptions) -> io::Result<Box<dyn BufRead>>
+where
+    R: BufRead + 'static,
+{
+    // A single read, e.g., from a pipe, can return fewer bytes than the longest header.
+    let mut header = Vec::with_capacity(CompressionFormat::DETECT_LEN);
+    (&mut reader)
+        .take(CompressionFormat::DETECT_LEN as u64)
+        .read_to_end(&mut header)?;
+
+    let format = CompressionFormat::detect(&header);
+    let reader = io::Cursor::new(header).chain(reader);
+
+    match format {
+        Some(format) => format.decoder(reader, options.threads),
+        None => Ok(Box::new(reader)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompress() -> io::Result<()> {
+        let data = b"@r0\nACGT\n+\nFQLB\n";
+
+        let mut buf = Vec::new();
+        decompress(&data[..], ReadOptions::default())?.read_to_end(&mut buf)?;
+        assert_eq!(buf, data);
+
+        if !cfg!(feature = "zstd") {
+            let zstd = io::Cursor::new(vec![0x28, 0xb5, 0x2f, 0xfd, 0x00]);
+            assert!(matches!(
+                decompress(zstd, ReadOptions::default()),
+                Err(e) if e.kind() == io::ErrorKind::Unsupported
+            ));
         }
-        _ => Ok(Reader::new(Box::new(reader))),
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_with_short_reads() -> io::Result<()> {
+        use flate2::{Compression, write::GzEncoder};
+
+        let data = b"@r0\nACGT\n+\nFQLB\n";
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        let compressed_data = encoder.finish()?;
+
+        // Each read returns a single byte, like a slow pipe.
+        let reader = BufReader::with_capacity(1, io::Cursor::new(compressed_data));
+
+        let mut buf = Vec::new();
+        decompress(reader, ReadOptions::default())?.read_to_end(&mut buf)?;
+        assert_eq!(buf, data);
+
+        let reader = BufReader::with_capacity(1, io::Cursor::new(data.to_vec()));
+
+        let mut buf = Vec::new();
+        decompress(reader, ReadOptions::default())?.read_to_end(&mut buf)?;
+        assert_eq!(buf, data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_stdio() {
+        assert!(is_stdio("-"));
+        assert!(!is_stdio("-.fq"));
+        assert!(!is_stdio("./-"));
+        assert!(!is_stdio("r1.fq"));
+    }
+
+    #[test]
+    fn test_has_multiple_stdio() {
+        assert!(!has_multiple_stdio::<_, &str>([]));
+        assert!(!has_multiple_stdio(["-"]));
+        assert!(!has_multiple_stdio(["-", "r1.fq"]));
+        assert!(has_multiple_stdio(["-", "r1.fq", "-"]));
     }
 }
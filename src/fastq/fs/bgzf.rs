@@ -0,0 +1,46 @@
+//! Blocked GNU Zip Format (BGZF).
+//!
+//! A BGZF file is a series of gzip members (blocks), each holding at most 64 KiB of compressed
+//! data, followed by an empty end-of-file block. The block size is stored in a gzip extra field,
+//! which allows blocks to be compressed and decompressed independently. Because each block is a
+//! complete gzip member, BGZF files are also readable by any multi-member gzip decoder.
+
+mod pool;
+mod reader;
+mod writer;
+
+pub(super) use self::reader::is_bgzf_header;
+pub use self::{reader::Reader, writer::Writer};
+
+// The maximum size of a block, including its header and footer.
+const MAX_BLOCK_SIZE: usize = 1 << 16;
+
+// The maximum number of uncompressed bytes in a block. This is less than the maximum block size
+// so that incompressible data still fits in a block when stored.
+const MAX_UNCOMPRESSED_BLOCK_SIZE: usize = 0xff00;
+
+// The number of blocks each worker can have queued before the oldest block is waited on.
+const QUEUE_DEPTH_PER_THREAD: usize = 4;
+
+pub(super) const HEADER_SIZE: usize = 18;
+const FOOTER_SIZE: usize = 8;
+
+// The header of a block without the block size (`BSIZE`) field, i.e., a gzip header with an extra
+// field (`FEXTRA`) holding a `BC` subfield of length 2.
+const HEADER_PREFIX: [u8; HEADER_SIZE - 2] = [
+    0x1f, 0x8b, // ID1, ID2
+    0x08, // CM = DEFLATE
+    0x04, // FLG = FEXTRA
+    0x00, 0x00, 0x00, 0x00, // MTIME
+    0x00, // XFL
+    0xff, // OS = unknown
+    0x06, 0x00, // XLEN
+    b'B', b'C', // SI1, SI2
+    0x02, 0x00, // SLEN
+];
+
+// The empty block that marks the end of a BGZF file.
+const EOF_BLOCK: [u8; 28] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00,
+    0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
@@ -0,0 +1,86 @@
+use std::{
+    io,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, mpsc},
+    thread::{self, JoinHandle},
+};
+
+pub(super) type BlockResult = io::Result<Vec<u8>>;
+
+type Job = (Vec<u8>, mpsc::SyncSender<BlockResult>);
+
+/// A pool of threads that transform blocks, e.g., compress or decompress them.
+///
+/// Each block is sent with its own result channel, so callers can receive results in the order
+/// the blocks were submitted.
+pub(super) struct Pool {
+    tx: Option<mpsc::Sender<Job>>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl Pool {
+    pub(super) fn new<F>(threads: NonZeroUsize, f: F) -> Self
+    where
+        F: Fn(Vec<u8>) -> BlockResult + Clone + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel::<Job>();
+        let rx = Arc::new(Mutex::new(rx));
+
+        let handles = (0..threads.get())
+            .map(|_| {
+                let rx = Arc::clone(&rx);
+                let f = f.clone();
+
+                thread::spawn(move || {
+                    loop {
+                        let job = match rx.lock() {
+                            Ok(rx) => rx.recv(),
+                            Err(_) => break,
+                        };
+
+                        let Ok((block, result_tx)) = job else {
+                            break;
+                        };
+
+                        let _ = result_tx.send(f(block));
+                    }
+                })
+            })
+            .collect();
+
+        Self {
+            tx: Some(tx),
+            handles,
+        }
+    }
+
+    /// Submits a block to be transformed and returns the channel that receives the result.
+    pub(super) fn submit(&self, block: Vec<u8>) -> io::Result<mpsc::Receiver<BlockResult>> {
+        let (result_tx, result_rx) = mpsc::sync_channel(1);
+
+        self.tx
+            .as_ref()
+            .expect("missing pool sender")
+            .send((block, result_tx))
+            .map_err(|_| io::Error::other("BGZF workers unexpectedly stopped"))?;
+
+        Ok(result_rx)
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        // Closing the channel stops the workers once the queue is empty.
+        drop(self.tx.take());
+
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Receives the result of a submitted block.
+pub(super) fn receive(rx: mpsc::Receiver<BlockResult>) -> BlockResult {
+    rx.recv()
+        .map_err(|_| io::Error::other("BGZF worker unexpectedly stopped"))?
+}
@@ -0,0 +1,274 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, Read},
+    num::NonZeroUsize,
+    sync::mpsc,
+};
+
+use flate2::{Crc, Decompress, FlushDecompress, Status};
+
+use super::{
+    FOOTER_SIZE, MAX_UNCOMPRESSED_BLOCK_SIZE, QUEUE_DEPTH_PER_THREAD,
+    pool::{self, BlockResult, Pool},
+};
+
+// The size of the gzip header up to and including the extra field length (`XLEN`).
+const FIXED_HEADER_SIZE: usize = 12;
+
+/// A BGZF reader that decompresses blocks on a pool of worker threads.
+///
+/// Compressed blocks are read on the calling thread, and decompressed blocks are returned in
+/// order.
+pub struct Reader<R> {
+    inner: R,
+    pool: Pool,
+    pending: VecDeque<mpsc::Receiver<BlockResult>>,
+    max_pending: usize,
+    is_eof: bool,
+    buf: Vec<u8>,
+    position: usize,
+}
+
+impl<R> Reader<R>
+where
+    R: BufRead,
+{
+    /// Creates a BGZF reader.
+    pub fn new(inner: R, threads: NonZeroUsize) -> Self {
+        Self {
+            inner,
+            pool: Pool::new(threads, |block: Vec<u8>| inflate_block(&block)),
+            pending: VecDeque::new(),
+            max_pending: threads.get() * QUEUE_DEPTH_PER_THREAD,
+            is_eof: false,
+            buf: Vec::new(),
+            position: 0,
+        }
+    }
+
+    fn submit_blocks(&mut self) -> io::Result<()> {
+        while !self.is_eof && self.pending.len() < self.max_pending {
+            match read_block(&mut self.inner)? {
+                Some(block) => self.pending.push_back(self.pool.submit(block)?),
+                None => self.is_eof = true,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R> Read for Reader<R>
+where
+    R: BufRead,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let src = self.fill_buf()?;
+        let n = src.len().min(buf.len());
+        buf[..n].copy_from_slice(&src[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R> BufRead for Reader<R>
+where
+    R: BufRead,
+{
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        // Blocks can be empty, e.g., the end-of-file block.
+        while self.position >= self.buf.len() {
+            self.submit_blocks()?;
+
+            match self.pending.pop_front() {
+                Some(rx) => {
+                    self.buf = pool::receive(rx)?;
+                    self.position = 0;
+                }
+                None => return Ok(&[]),
+            }
+        }
+
+        Ok(&self.buf[self.position..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.position = (self.position + amt).min(self.buf.len());
+    }
+}
+
+/// Returns whether the start of a stream is a BGZF block header.
+pub(crate) fn is_bgzf_header(buf: &[u8]) -> bool {
+    if buf.len() < FIXED_HEADER_SIZE {
+        return false;
+    }
+
+    let is_gzip_with_extra_field = buf.starts_with(&[0x1f, 0x8b, 0x08]) && buf[3] & 0x04 != 0;
+    let xlen = usize::from(u16::from_le_bytes([buf[10], buf[11]]));
+    let end = (FIXED_HEADER_SIZE + xlen).min(buf.len());
+
+    is_gzip_with_extra_field && find_block_size(&buf[FIXED_HEADER_SIZE..end]).is_some()
+}
+
+// Reads a raw block, including its header and footer.
+fn read_block<R>(reader: &mut R) -> io::Result<Option<Vec<u8>>>
+where
+    R: BufRead,
+{
+    if reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+
+    let mut block = vec![0; FIXED_HEADER_SIZE];
+    reader.read_exact(&mut block)?;
+
+    if !block.starts_with(&[0x1f, 0x8b, 0x08]) || block[3] & 0x04 == 0 {
+        return Err(invalid_block("invalid header"));
+    }
+
+    let xlen = usize::from(u16::from_le_bytes([block[10], block[11]]));
+    block.resize(FIXED_HEADER_SIZE + xlen, 0);
+    reader.read_exact(&mut block[FIXED_HEADER_SIZE..])?;
+
+    let block_size = find_block_size(&block[FIXED_HEADER_SIZE..])
+        .ok_or_else(|| invalid_block("missing block size"))?;
+
+    if block_size < block.len() + FOOTER_SIZE {
+        return Err(invalid_block("invalid block size"));
+    }
+
+    let header_size = block.len();
+    block.resize(block_size, 0);
+    reader.read_exact(&mut block[header_size..])?;
+
+    Ok(Some(block))
+}
+
+// Finds the block size in the `BC` subfield of a gzip extra field.
+fn find_block_size(mut extra: &[u8]) -> Option<usize> {
+    while extra.len() >= 4 {
+        let id = [extra[0], extra[1]];
+        let len = usize::from(u16::from_le_bytes([extra[2], extra[3]]));
+        let data = extra.get(4..4 + len)?;
+
+        if id == *b"BC" && len == 2 {
+            let bsize = u16::from_le_bytes([data[0], data[1]]);
+            return Some(usize::from(bsize) + 1);
+        }
+
+        extra = &extra[4 + len..];
+    }
+
+    None
+}
+
+fn inflate_block(block: &[u8]) -> BlockResult {
+    let xlen = usize::from(u16::from_le_bytes([block[10], block[11]]));
+    let header_size = FIXED_HEADER_SIZE + xlen;
+    let (cdata, footer) = block[header_size..].split_at(block.len() - header_size - FOOTER_SIZE);
+
+    let expected_crc32 = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]);
+    let isize = u32::from_le_bytes([footer[4], footer[5], footer[6], footer[7]]);
+    let len = usize::try_from(isize).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    if len > MAX_UNCOMPRESSED_BLOCK_SIZE.next_power_of_two() {
+        return Err(invalid_block("invalid uncompressed size"));
+    }
+
+    let mut data = Vec::with_capacity(len);
+    let mut decompress = Decompress::new(false);
+
+    let status = decompress
+        .decompress_vec(cdata, &mut data, FlushDecompress::Finish)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    if status != Status::StreamEnd || data.len() != len {
+        return Err(invalid_block("uncompressed size mismatch"));
+    }
+
+    let mut crc = Crc::new();
+    crc.update(&data);
+
+    if crc.sum() != expected_crc32 {
+        return Err(invalid_block("checksum mismatch"));
+    }
+
+    Ok(data)
+}
+
+fn invalid_block(message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid BGZF block: {message}"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{Compression, write::GzEncoder};
+
+    use super::*;
+    use crate::fastq::fs::bgzf::Writer;
+
+    fn build_data() -> Vec<u8> {
+        (0..20000)
+            .flat_map(|i| format!("@r{i}\nACGTACGTAC\n+\nFQLBFQLBFQ\n").into_bytes())
+            .collect()
+    }
+
+    fn compress(data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut writer = Writer::new(Vec::new(), Compression::default(), NonZeroUsize::MIN);
+        writer.write_all(data)?;
+        writer.finish()
+    }
+
+    #[test]
+    fn test_read() -> io::Result<()> {
+        let data = build_data();
+        let src = compress(&data)?;
+
+        for threads in [1, 4] {
+            let threads = NonZeroUsize::new(threads).unwrap();
+            let mut reader = Reader::new(&src[..], threads);
+
+            let mut buf = Vec::new();
+            reader.read_to_end(&mut buf)?;
+            assert_eq!(buf, data);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_with_corrupt_block() -> io::Result<()> {
+        let mut src = compress(b"@r0\nACGT\n+\nFQLB\n")?;
+        src[20] ^= 0xff;
+
+        let mut reader = Reader::new(&src[..], NonZeroUsize::MIN);
+        let mut buf = Vec::new();
+
+        assert!(matches!(
+            reader.read_to_end(&mut buf),
+            Err(e) if e.kind() == io::ErrorKind::InvalidData
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_bgzf_header() -> io::Result<()> {
+        let src = compress(b"@r0\nACGT\n+\nFQLB\n")?;
+        assert!(is_bgzf_header(&src));
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"@r0\nACGT\n+\nFQLB\n")?;
+        let src = encoder.finish()?;
+        assert!(!is_bgzf_header(&src));
+
+        assert!(!is_bgzf_header(b"@r0\nACGT\n+\nFQLB\n"));
+
+        Ok(())
+    }
+}
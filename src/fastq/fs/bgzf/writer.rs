@@ -0,0 +1,259 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+    mem,
+    num::NonZeroUsize,
+    sync::mpsc,
+};
+
+use flate2::{Compression, Crc, write::DeflateEncoder};
+
+use super::{
+    EOF_BLOCK, FOOTER_SIZE, HEADER_PREFIX, HEADER_SIZE, MAX_BLOCK_SIZE,
+    MAX_UNCOMPRESSED_BLOCK_SIZE, QUEUE_DEPTH_PER_THREAD,
+    pool::{self, BlockResult, Pool},
+};
+
+/// A BGZF writer.
+///
+/// When given more than one thread, blocks are compressed on a pool of worker threads and written
+/// in order. The end-of-file block is written when the writer is finished or dropped.
+pub struct Writer<W>
+where
+    W: Write,
+{
+    inner: Option<W>,
+    buf: Vec<u8>,
+    compression: Compression,
+    pool: Option<Pool>,
+    pending: VecDeque<mpsc::Receiver<BlockResult>>,
+    max_pending: usize,
+}
+
+impl<W> Writer<W>
+where
+    W: Write,
+{
+    /// Creates a BGZF writer.
+    pub fn new(inner: W, compression: Compression, threads: NonZeroUsize) -> Self {
+        let pool = (threads.get() > 1).then(|| {
+            Pool::new(threads, move |data: Vec<u8>| {
+                compress_block(&data, compression)
+            })
+        });
+
+        Self {
+            inner: Some(inner),
+            buf: Vec::with_capacity(MAX_UNCOMPRESSED_BLOCK_SIZE),
+            compression,
+            pool,
+            pending: VecDeque::new(),
+            max_pending: threads.get() * QUEUE_DEPTH_PER_THREAD,
+        }
+    }
+
+    /// Writes all remaining blocks and the end-of-file block and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.try_finish()?;
+        Ok(self.inner.take().expect("missing inner writer"))
+    }
+
+    fn try_finish(&mut self) -> io::Result<()> {
+        self.flush()?;
+
+        let inner = self.get_mut();
+        inner.write_all(&EOF_BLOCK)?;
+        inner.flush()
+    }
+
+    fn get_mut(&mut self) -> &mut W {
+        self.inner.as_mut().expect("missing inner writer")
+    }
+
+    fn flush_block(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+
+        let data = mem::replace(
+            &mut self.buf,
+            Vec::with_capacity(MAX_UNCOMPRESSED_BLOCK_SIZE),
+        );
+
+        if let Some(pool) = &self.pool {
+            self.pending.push_back(pool.submit(data)?);
+
+            while self.pending.len() > self.max_pending {
+                self.write_pending_block()?;
+            }
+        } else {
+            let block = compress_block(&data, self.compression)?;
+            self.get_mut().write_all(&block)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_pending_block(&mut self) -> io::Result<()> {
+        if let Some(rx) = self.pending.pop_front() {
+            let block = pool::receive(rx)?;
+            self.get_mut().write_all(&block)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W> Write for Writer<W>
+where
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(MAX_UNCOMPRESSED_BLOCK_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&buf[..n]);
+
+        if self.buf.len() >= MAX_UNCOMPRESSED_BLOCK_SIZE {
+            self.flush_block()?;
+        }
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_block()?;
+
+        while !self.pending.is_empty() {
+            self.write_pending_block()?;
+        }
+
+        self.get_mut().flush()
+    }
+}
+
+impl<W> Drop for Writer<W>
+where
+    W: Write,
+{
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.try_finish();
+        }
+    }
+}
+
+fn compress_block(data: &[u8], compression: Compression) -> io::Result<Vec<u8>> {
+    let mut cdata = deflate(data, compression)?;
+
+    if HEADER_SIZE + cdata.len() + FOOTER_SIZE > MAX_BLOCK_SIZE {
+        cdata = deflate(data, Compression::none())?;
+    }
+
+    let block_size = HEADER_SIZE + cdata.len() + FOOTER_SIZE;
+    let bsize = u16::try_from(block_size - 1)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let mut crc = Crc::new();
+    crc.update(data);
+
+    let isize =
+        u32::try_from(data.len()).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let mut block = Vec::with_capacity(block_size);
+    block.extend_from_slice(&HEADER_PREFIX);
+    block.extend_from_slice(&bsize.to_le_bytes());
+    block.extend_from_slice(&cdata);
+    block.extend_from_slice(&crc.sum().to_le_bytes());
+    block.extend_from_slice(&isize.to_le_bytes());
+
+    Ok(block)
+}
+
+fn deflate(data: &[u8], compression: Compression) -> io::Result<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), compression);
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::MultiGzDecoder;
+
+    use super::*;
+
+    fn build_data() -> Vec<u8> {
+        (0..20000)
+            .flat_map(|i| format!("@r{i}\nACGTACGTAC\n+\nFQLBFQLBFQ\n").into_bytes())
+            .collect()
+    }
+
+    // Returns the size of each block, using the block size in the header.
+    fn block_sizes(mut src: &[u8]) -> Vec<usize> {
+        let mut sizes = Vec::new();
+
+        while !src.is_empty() {
+            assert_eq!(src[..HEADER_PREFIX.len()], HEADER_PREFIX);
+            let bsize = u16::from_le_bytes([src[16], src[17]]);
+            let size = usize::from(bsize) + 1;
+            sizes.push(size);
+            src = &src[size..];
+        }
+
+        sizes
+    }
+
+    #[test]
+    fn test_write() -> io::Result<()> {
+        let data = build_data();
+
+        for threads in [1, 4] {
+            let threads = NonZeroUsize::new(threads).unwrap();
+            let mut writer = Writer::new(Vec::new(), Compression::default(), threads);
+            writer.write_all(&data)?;
+            let dst = writer.finish()?;
+
+            assert!(dst.ends_with(&EOF_BLOCK));
+
+            let sizes = block_sizes(&dst);
+            assert!(sizes.len() > 2);
+            assert!(sizes.iter().all(|&size| size <= MAX_BLOCK_SIZE));
+
+            let mut buf = Vec::new();
+            MultiGzDecoder::new(&dst[..]).read_to_end(&mut buf)?;
+            assert_eq!(buf, data);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_with_no_data() -> io::Result<()> {
+        let writer = Writer::new(Vec::new(), Compression::default(), NonZeroUsize::MIN);
+        assert_eq!(writer.finish()?, EOF_BLOCK);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_block_with_incompressible_data() -> io::Result<()> {
+        let mut state = 0x2545f4914f6cdd1d_u64;
+
+        let data: Vec<u8> = (0..MAX_UNCOMPRESSED_BLOCK_SIZE)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+
+        let block = compress_block(&data, Compression::best())?;
+        assert!(block.len() <= MAX_BLOCK_SIZE);
+
+        let mut buf = Vec::new();
+        MultiGzDecoder::new(&block[..]).read_to_end(&mut buf)?;
+        assert_eq!(buf, data);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,345 @@
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    num::NonZeroUsize,
+    ops::RangeInclusive,
+    path::Path,
+};
+
+use flate2::{Compression, bufread::MultiGzDecoder, write::GzEncoder};
+
+use super::bgzf;
+
+const GZ_MAGIC_NUMBER: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC_NUMBER: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const BZ2_MAGIC_NUMBER: [u8; 3] = *b"BZh";
+const XZ_MAGIC_NUMBER: [u8; 6] = [0xfd, b'7', b'z', b'X', b'Z', 0x00];
+
+#[cfg(feature = "xz")]
+const DEFAULT_XZ_LEVEL: u32 = 6;
+
+/// A compression format.
+///
+/// gzip and BGZF are always supported. zstd, bzip2, and xz require the `zstd`, `bzip2`, and `xz`
+/// features, respectively.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum CompressionFormat {
+    /// gzip.
+    Gzip,
+    /// Blocked GNU Zip Format (BGZF), a gzip variant that can be compressed in parallel.
+    Bgzf,
+    /// Zstandard.
+    Zstd,
+    /// bzip2.
+    Bzip2,
+    /// xz.
+    Xz,
+}
+
+impl CompressionFormat {
+    /// The number of bytes at the start of a stream needed to detect any format, i.e., the size
+    /// of a BGZF block header.
+    pub const DETECT_LEN: usize = bgzf::HEADER_SIZE;
+
+    /// Detects the compression format from the start of a stream.
+    ///
+    /// This returns `None` if the stream is not compressed by a known format. `buf` should hold at
+    /// least [`Self::DETECT_LEN`] bytes, unless the stream is shorter.
+    pub fn detect(buf: &[u8]) -> Option<Self> {
+        if bgzf::is_bgzf_header(buf) {
+            Some(Self::Bgzf)
+        } else if buf.starts_with(&GZ_MAGIC_NUMBER) {
+            Some(Self::Gzip)
+        } else if buf.starts_with(&ZSTD_MAGIC_NUMBER) {
+            Some(Self::Zstd)
+        } else if buf.starts_with(&BZ2_MAGIC_NUMBER) {
+            Some(Self::Bzip2)
+        } else if buf.starts_with(&XZ_MAGIC_NUMBER) {
+            Some(Self::Xz)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the compression format associated with the extension of the path, if any.
+    pub fn from_path<P>(path: P) -> Option<Self>
+    where
+        P: AsRef<Path>,
+    {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Some(Self::Gzip),
+            Some("bgz") => Some(Self::Bgzf),
+            Some("zst") => Some(Self::Zstd),
+            Some("bz2") => Some(Self::Bzip2),
+            Some("xz") => Some(Self::Xz),
+            _ => None,
+        }
+    }
+
+    /// Wraps a reader with a decoder for this format.
+    ///
+    /// `threads` is only used by BGZF. If it is 1, BGZF is decoded as gzip.
+    pub(super) fn decoder<R>(self, reader: R, threads: NonZeroUsize) -> io::Result<Box<dyn BufRead>>
+    where
+        R: BufRead + 'static,
+    {
+        match self {
+            Self::Bgzf if threads.get() > 1 => Ok(Box::new(bgzf::Reader::new(reader, threads))),
+            Self::Gzip | Self::Bgzf => Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader)))),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => {
+                let decoder = zstd::stream::read::Decoder::with_buffer(reader)?;
+                Ok(Box::new(BufReader::new(decoder)))
+            }
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2 => {
+                let decoder = bzip2::bufread::MultiBzDecoder::new(reader);
+                Ok(Box::new(BufReader::new(decoder)))
+            }
+            #[cfg(feature = "xz")]
+            Self::Xz => {
+                let decoder = liblzma::bufread::XzDecoder::new_multi_decoder(reader);
+                Ok(Box::new(BufReader::new(decoder)))
+            }
+            #[cfg(not(all(feature = "zstd", feature = "bzip2", feature = "xz")))]
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    /// Wraps a writer with an encoder for this format.
+    ///
+    /// If `level` is `None`, the default level of the format is used. `threads` is only used by
+    /// BGZF.
+    pub(super) fn encoder<W>(
+        self,
+        writer: W,
+        level: Option<u32>,
+        threads: NonZeroUsize,
+    ) -> io::Result<Box<dyn Write>>
+    where
+        W: Write + 'static,
+    {
+        self.validate(level)?;
+
+        match self {
+            Self::Gzip => {
+                let compression = level.map(Compression::new).unwrap_or_default();
+                Ok(Box::new(GzEncoder::new(writer, compression)))
+            }
+            Self::Bgzf => {
+                let compression = level.map(Compression::new).unwrap_or_default();
+                Ok(Box::new(bgzf::Writer::new(writer, compression, threads)))
+            }
+            #[cfg(feature = "zstd")]
+            Self::Zstd => {
+                // 0 is zstd's default level.
+                let level = level.map(|n| n as i32).unwrap_or(0);
+                let encoder = zstd::stream::write::Encoder::new(writer, level)?;
+                Ok(Box::new(encoder.auto_finish()))
+            }
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2 => {
+                let compression = level.map(bzip2::Compression::new).unwrap_or_default();
+                Ok(Box::new(bzip2::write::BzEncoder::new(writer, compression)))
+            }
+            #[cfg(feature = "xz")]
+            Self::Xz => {
+                let level = level.unwrap_or(DEFAULT_XZ_LEVEL);
+                Ok(Box::new(liblzma::write::XzEncoder::new(writer, level)))
+            }
+            #[cfg(not(all(feature = "zstd", feature = "bzip2", feature = "xz")))]
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    /// Checks that support for this format is enabled and that the compression level, if any, is
+    /// valid for this format.
+    pub(super) fn validate(self, level: Option<u32>) -> io::Result<()> {
+        if !self.is_enabled() {
+            return Err(self.unsupported());
+        }
+
+        let levels = self.levels();
+
+        match level {
+            Some(n) if !levels.contains(&n) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "invalid compression level for {self}: expected {}..={}, got {n}",
+                    levels.start(),
+                    levels.end()
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn levels(self) -> RangeInclusive<u32> {
+        match self {
+            Self::Gzip | Self::Bgzf | Self::Xz => 0..=9,
+            Self::Zstd => 1..=22,
+            Self::Bzip2 => 1..=9,
+        }
+    }
+
+    /// Returns whether support for this format is enabled.
+    pub fn is_enabled(self) -> bool {
+        match self {
+            Self::Gzip | Self::Bgzf => true,
+            Self::Zstd => cfg!(feature = "zstd"),
+            Self::Bzip2 => cfg!(feature = "bzip2"),
+            Self::Xz => cfg!(feature = "xz"),
+        }
+    }
+
+    fn unsupported(self) -> io::Error {
+        let feature = match self {
+            Self::Gzip | Self::Bgzf => unreachable!("{self} is always supported"),
+            Self::Zstd => "zstd",
+            Self::Bzip2 => "bzip2",
+            Self::Xz => "xz",
+        };
+
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("unsupported compression format: {self} (rebuild with `--features {feature}`)"),
+        )
+    }
+}
+
+impl fmt::Display for CompressionFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gzip => f.write_str("gzip"),
+            Self::Bgzf => f.write_str("BGZF"),
+            Self::Zstd => f.write_str("zstd"),
+            Self::Bzip2 => f.write_str("bzip2"),
+            Self::Xz => f.write_str("xz"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            CompressionFormat::detect(&[0x1f, 0x8b, 0x08, 0x04]),
+            Some(CompressionFormat::Gzip)
+        );
+        assert_eq!(
+            CompressionFormat::detect(&[0x28, 0xb5, 0x2f, 0xfd, 0x00]),
+            Some(CompressionFormat::Zstd)
+        );
+        assert_eq!(
+            CompressionFormat::detect(b"BZh91AY&SY"),
+            Some(CompressionFormat::Bzip2)
+        );
+        assert_eq!(
+            CompressionFormat::detect(&[0xfd, b'7', b'z', b'X', b'Z', 0x00, 0x00]),
+            Some(CompressionFormat::Xz)
+        );
+        assert_eq!(CompressionFormat::detect(b"@r0\nACGT\n"), None);
+        assert_eq!(CompressionFormat::detect(&[0x1f]), None);
+        assert_eq!(CompressionFormat::detect(&[]), None);
+    }
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(
+            CompressionFormat::from_path("r1.fq.gz"),
+            Some(CompressionFormat::Gzip)
+        );
+        assert_eq!(
+            CompressionFormat::from_path("r1.fq.bgz"),
+            Some(CompressionFormat::Bgzf)
+        );
+        assert_eq!(
+            CompressionFormat::from_path("r1.fq.zst"),
+            Some(CompressionFormat::Zstd)
+        );
+        assert_eq!(
+            CompressionFormat::from_path("r1.fq.bz2"),
+            Some(CompressionFormat::Bzip2)
+        );
+        assert_eq!(
+            CompressionFormat::from_path("r1.fq.xz"),
+            Some(CompressionFormat::Xz)
+        );
+        assert_eq!(CompressionFormat::from_path("r1.fq"), None);
+        assert_eq!(CompressionFormat::from_path("-"), None);
+    }
+
+    #[test]
+    fn test_round_trip() -> io::Result<()> {
+        const DATA: &[u8] = b"@r0\nACGT\n+\nFQLB\n";
+
+        let formats = [
+            (CompressionFormat::Gzip, true),
+            (CompressionFormat::Bgzf, true),
+            (CompressionFormat::Zstd, cfg!(feature = "zstd")),
+            (CompressionFormat::Bzip2, cfg!(feature = "bzip2")),
+            (CompressionFormat::Xz, cfg!(feature = "xz")),
+        ];
+
+        for (format, is_enabled) in formats {
+            let (tx, rx) = std::sync::mpsc::channel();
+
+            match format.encoder(ChannelWriter(tx), None, NonZeroUsize::MIN) {
+                Ok(mut encoder) => {
+                    assert!(is_enabled);
+                    encoder.write_all(DATA)?;
+                }
+                Err(e) => {
+                    assert!(!is_enabled);
+                    assert_eq!(e.kind(), io::ErrorKind::Unsupported);
+                    continue;
+                }
+            }
+
+            let compressed: Vec<u8> = rx.iter().flatten().collect();
+
+            assert_eq!(CompressionFormat::detect(&compressed), Some(format));
+
+            let mut buf = Vec::new();
+            format
+                .decoder(io::Cursor::new(compressed), NonZeroUsize::MIN)?
+                .read_to_end(&mut buf)?;
+            assert_eq!(buf, DATA);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(CompressionFormat::Gzip.validate(None).is_ok());
+        assert!(CompressionFormat::Gzip.validate(Some(9)).is_ok());
+        assert!(CompressionFormat::Bgzf.validate(Some(0)).is_ok());
+
+        assert!(matches!(
+            CompressionFormat::Gzip.validate(Some(10)),
+            Err(e) if e.kind() == io::ErrorKind::InvalidInput
+        ));
+    }
+
+    // Encoders are boxed and finish on drop, so the output is collected through a channel.
+    struct ChannelWriter(std::sync::mpsc::Sender<Vec<u8>>);
+
+    impl Write for ChannelWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0
+                .send(buf.to_vec())
+                .map_err(|e| io::Error::new(io::ErrorKind::BrokenPipe, e))?;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
@@ -46,7 +46,8 @@ impl Record {
         }
     }
 
-    fn definition(&self) -> &[u8] {
+    /// Returns the full definition line, i.e., the name and description.
+    pub fn definition(&self) -> &[u8] {
         trim_newline_end(&self.buf[0..self.definition_end])
     }
 
@@ -4,6 +4,9 @@ mod metric;
 mod min_sequence_length;
 mod record_count;
 
+pub(crate) use self::avg_quality_score_per_position::{
+    decode_score, error_probability_to_phred_score, phred_score_to_error_probability,
+};
 pub use self::metric::Metric;
 use self::{
     avg_quality_score_per_position::AvgQualityScorePerPosition,
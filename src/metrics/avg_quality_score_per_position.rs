@@ -1,5 +1,7 @@
 use std::io;
 
+use serde_json::Value;
+
 use super::Metric;
 use crate::fastq::Record;
 
@@ -17,6 +19,10 @@ pub struct AvgQualityScorePerPosition {
 }
 
 impl Metric for AvgQualityScorePerPosition {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
     fn visit(&mut self, record: &Record) -> io::Result<()> {
         let read_length = record.sequence().len();
 
@@ -39,7 +45,7 @@ impl Metric for AvgQualityScorePerPosition {
         Ok(())
     }
 
-    fn println(&self) {
+    fn value(&self) -> Value {
         let avg_quality_score_per_position: Vec<_> = self
             .error_probability_sums_per_position
             .iter()
@@ -50,11 +56,11 @@ impl Metric for AvgQualityScorePerPosition {
             })
             .collect();
 
-        println!("{NAME}\t{avg_quality_score_per_position:?}");
+        Value::from(avg_quality_score_per_position)
     }
 }
 
-fn decode_score(c: u8) -> io::Result<u8> {
+pub(crate) fn decode_score(c: u8) -> io::Result<u8> {
     const OFFSET: u8 = b'!';
 
     c.checked_sub(OFFSET)
@@ -65,11 +71,11 @@ fn decode_score(c: u8) -> io::Result<u8> {
 const BASE: f64 = 10.0;
 const FACTOR: f64 = 10.0;
 
-fn phred_score_to_error_probability(n: u8) -> f64 {
+pub(crate) fn phred_score_to_error_probability(n: u8) -> f64 {
     BASE.powf(-f64::from(n) / FACTOR)
 }
 
-fn error_probability_to_phred_score(p: f64) -> f64 {
+pub(crate) fn error_probability_to_phred_score(p: f64) -> f64 {
     -FACTOR * p.log10()
 }
 
@@ -77,6 +83,18 @@ fn error_probability_to_phred_score(p: f64) -> f64 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_value() -> io::Result<()> {
+        let mut metric = AvgQualityScorePerPosition::default();
+        assert_eq!(metric.value(), Value::Array(Vec::new()));
+
+        metric.visit(&Record::new("", "AC", "", "55"))?;
+        metric.visit(&Record::new("", "A", "", "5"))?;
+        assert_eq!(metric.value(), Value::from(vec![20.0, 20.0]));
+
+        Ok(())
+    }
+
     #[test]
     fn test_decode_score() -> io::Result<()> {
         assert_eq!(decode_score(b'!')?, 0);
@@ -1,5 +1,7 @@
 use std::io;
 
+use serde_json::Value;
+
 use super::Metric;
 use crate::fastq::Record;
 
@@ -9,14 +11,18 @@ const NAME: &str = "max_sequence_length";
 pub struct MaxSequenceLength(usize);
 
 impl Metric for MaxSequenceLength {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
     fn visit(&mut self, record: &Record) -> io::Result<()> {
         let read_length = record.sequence().len();
         self.0 = self.0.max(read_length);
         Ok(())
     }
 
-    fn println(&self) {
-        println!("{NAME}\t{}", self.0);
+    fn value(&self) -> Value {
+        Value::from(self.0)
     }
 }
 
@@ -1,8 +1,15 @@
 use std::io;
 
+use serde_json::Value;
+
 use crate::fastq::Record;
 
 pub trait Metric {
+    /// Returns the name of the metric.
+    fn name(&self) -> &'static str;
+
     fn visit(&mut self, record: &Record) -> io::Result<()>;
-    fn println(&self);
+
+    /// Returns the serializable value of the metric.
+    fn value(&self) -> Value;
 }
@@ -1,5 +1,7 @@
 use std::io;
 
+use serde_json::Value;
+
 use super::Metric;
 use crate::fastq::Record;
 
@@ -20,6 +22,10 @@ impl Default for MinSequenceLength {
 }
 
 impl Metric for MinSequenceLength {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
     fn visit(&mut self, record: &Record) -> io::Result<()> {
         self.initialized = true;
 
@@ -29,8 +35,12 @@ impl Metric for MinSequenceLength {
         Ok(())
     }
 
-    fn println(&self) {
-        println!("{NAME}\t{}", self.len);
+    fn value(&self) -> Value {
+        if self.initialized {
+            Value::from(self.len)
+        } else {
+            Value::Null
+        }
     }
 }
 
@@ -51,4 +61,16 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_value() -> io::Result<()> {
+        let mut metric = MinSequenceLength::default();
+        assert_eq!(metric.value(), Value::Null);
+
+        let record = Record::new("", "ACGT", "", "");
+        metric.visit(&record)?;
+        assert_eq!(metric.value(), Value::from(4));
+
+        Ok(())
+    }
 }
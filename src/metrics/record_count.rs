@@ -1,5 +1,7 @@
 use std::io;
 
+use serde_json::Value;
+
 use super::Metric;
 use crate::fastq::Record;
 
@@ -9,13 +11,17 @@ const NAME: &str = "record_count";
 pub struct RecordCount(u64);
 
 impl Metric for RecordCount {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
     fn visit(&mut self, _: &Record) -> io::Result<()> {
         self.0 += 1;
         Ok(())
     }
 
-    fn println(&self) {
-        println!("{NAME}\t{}", self.0);
+    fn value(&self) -> Value {
+        Value::from(self.0)
     }
 }
 
@@ -3,10 +3,12 @@ pub mod single;
 pub mod validation_level;
 
 pub use self::{
-    paired::{NamesValidator, PairedReadValidator},
+    paired::{NamesValidator, PairedReadValidator, ReadNumbersValidator},
     single::{
-        AlphabetValidator, CompleteValidator, ConsistentSeqQualValidator, NameValidator,
-        PlusLineValidator, QualityStringValidator, SingleReadValidator, SingleReadValidatorMut,
+        Alphabet, AlphabetValidator, CasavaHeaderValidator, CompleteValidator,
+        ConsistentSeqQualValidator, NameValidator, PlusLineValidator, QualityEncoding,
+        QualityEncodingValidator, QualityStringValidator, SingleReadValidator,
+        SingleReadValidatorMut,
     },
     validation_level::ValidationLevel,
 };
@@ -72,6 +74,9 @@ impl fmt::Display for Error {
 
 impl error::Error for Error {}
 
+/// The codes of validators that are only used when enabled.
+pub const OPT_IN_VALIDATOR_CODES: [&str; 2] = ["S009", "P002"];
+
 #[derive(clap::ValueEnum, Clone, Copy, Debug, Eq, PartialEq)]
 pub enum LintMode {
     Panic,
@@ -82,11 +87,19 @@ pub fn filter_validators(
     single_read_validation_level: ValidationLevel,
     paired_read_validation_level: Option<ValidationLevel>,
     disabled_validators: &[String],
+    enabled_validators: &[String],
+    alphabet: &Alphabet,
+    quality_encoding: Option<QualityEncoding>,
 ) -> SingleAndPairedValidators {
     info!("disabled validators: {:?}", disabled_validators);
 
-    let single_read_validators =
-        filter_single_read_validators(single_read_validation_level, disabled_validators);
+    let single_read_validators = filter_single_read_validators(
+        single_read_validation_level,
+        disabled_validators,
+        enabled_validators,
+        alphabet,
+        quality_encoding,
+    );
 
     let validators: Vec<String> = single_read_validators
         .iter()
@@ -96,7 +109,7 @@ pub fn filter_validators(
     info!("enabled single read validators: {:?}", validators);
 
     let paired_read_validators = paired_read_validation_level
-        .map(|level| filter_paired_read_validators(level, disabled_validators))
+        .map(|level| filter_paired_read_validators(level, disabled_validators, enabled_validators))
         .unwrap_or_default();
 
     let validators: Vec<String> = paired_read_validators
@@ -112,16 +125,33 @@ pub fn filter_validators(
 fn filter_single_read_validators(
     validation_level: ValidationLevel,
     disabled_validators: &[String],
+    enabled_validators: &[String],
+    alphabet: &Alphabet,
+    quality_encoding: Option<QualityEncoding>,
 ) -> Vec<Box<dyn SingleReadValidator>> {
-    let single_read_validators: Vec<Box<dyn SingleReadValidator>> = vec![
+    let mut single_read_validators: Vec<Box<dyn SingleReadValidator>> = vec![
         Box::new(NameValidator),
         Box::new(CompleteValidator),
-        Box::<AlphabetValidator>::default(),
+        Box::new(AlphabetValidator::from(alphabet)),
         Box::new(PlusLineValidator),
         Box::new(ConsistentSeqQualValidator),
         Box::new(QualityStringValidator),
     ];
 
+    if let Some(encoding) = quality_encoding {
+        single_read_validators.push(Box::new(QualityEncodingValidator::new(encoding)));
+    }
+
+    // Opt-in validators are only used when enabled by code.
+    let opt_in_validators: Vec<Box<dyn SingleReadValidator>> =
+        vec![Box::new(CasavaHeaderValidator::new())];
+
+    single_read_validators.extend(
+        opt_in_validators
+            .into_iter()
+            .filter(|v| enabled_validators.contains(&v.code().to_string())),
+    );
+
     single_read_validators
         .into_iter()
         .filter(|v| v.level() <= validation_level)
@@ -132,8 +162,19 @@ fn filter_single_read_validators(
 fn filter_paired_read_validators(
     validation_level: ValidationLevel,
     disabled_validators: &[String],
+    enabled_validators: &[String],
 ) -> Vec<Box<dyn PairedReadValidator>> {
-    let paired_read_validators: Vec<Box<dyn PairedReadValidator>> = vec![Box::new(NamesValidator)];
+    let mut paired_read_validators: Vec<Box<dyn PairedReadValidator>> =
+        vec![Box::new(NamesValidator)];
+
+    // Opt-in validators are only used when enabled by code.
+    let opt_in_validators: Vec<Box<dyn PairedReadValidator>> = vec![Box::new(ReadNumbersValidator)];
+
+    paired_read_validators.extend(
+        opt_in_validators
+            .into_iter()
+            .filter(|v| enabled_validators.contains(&v.code().to_string())),
+    );
 
     paired_read_validators
         .into_iter()
@@ -148,14 +189,26 @@ mod tests {
 
     #[test]
     fn test_filter_validators() {
-        let (single_read_validators, paired_read_validators) =
-            filter_validators(ValidationLevel::High, None, &[]);
+        let (single_read_validators, paired_read_validators) = filter_validators(
+            ValidationLevel::High,
+            None,
+            &[],
+            &[],
+            &Alphabet::default(),
+            None,
+        );
 
         assert_eq!(single_read_validators.len(), 6);
         assert_eq!(paired_read_validators.len(), 0);
 
-        let (single_read_validators, paired_read_validators) =
-            filter_validators(ValidationLevel::High, Some(ValidationLevel::High), &[]);
+        let (single_read_validators, paired_read_validators) = filter_validators(
+            ValidationLevel::High,
+            Some(ValidationLevel::High),
+            &[],
+            &[],
+            &Alphabet::default(),
+            None,
+        );
 
         assert_eq!(single_read_validators.len(), 6);
         assert_eq!(paired_read_validators.len(), 1);
@@ -165,13 +218,25 @@ mod tests {
     fn test_filter_single_read_validators() {
         let disabled_validators = Vec::new();
 
-        let validators = filter_single_read_validators(ValidationLevel::Low, &disabled_validators);
+        let validators = filter_single_read_validators(
+            ValidationLevel::Low,
+            &disabled_validators,
+            &[],
+            &Alphabet::default(),
+            None,
+        );
 
         assert_eq!(validators.len(), 2);
         assert_eq!(validators[0].name(), "CompleteValidator");
         assert_eq!(validators[1].name(), "PlusLineValidator");
 
-        let validators = filter_single_read_validators(ValidationLevel::High, &disabled_validators);
+        let validators = filter_single_read_validators(
+            ValidationLevel::High,
+            &disabled_validators,
+            &[],
+            &Alphabet::default(),
+            None,
+        );
 
         assert_eq!(validators.len(), 6);
     }
@@ -180,21 +245,77 @@ mod tests {
     fn test_filter_single_read_validators_with_disabled_validators() {
         let disabled_validators = vec![String::from("S001")];
 
-        let validators = filter_single_read_validators(ValidationLevel::High, &disabled_validators);
+        let validators = filter_single_read_validators(
+            ValidationLevel::High,
+            &disabled_validators,
+            &[],
+            &Alphabet::default(),
+            None,
+        );
 
         assert_eq!(validators.len(), 5);
         assert!(!validators.iter().any(|v| v.code() == "S001"));
     }
 
+    #[test]
+    fn test_filter_single_read_validators_with_quality_encoding() {
+        let validators = filter_single_read_validators(
+            ValidationLevel::High,
+            &[],
+            &[],
+            &Alphabet::default(),
+            Some(QualityEncoding::Phred64),
+        );
+
+        assert_eq!(validators.len(), 7);
+        assert_eq!(validators[6].code(), "S008");
+
+        let validators = filter_single_read_validators(
+            ValidationLevel::High,
+            &[String::from("S008")],
+            &[],
+            &Alphabet::default(),
+            Some(QualityEncoding::Phred64),
+        );
+
+        assert_eq!(validators.len(), 6);
+    }
+
+    #[test]
+    fn test_filter_single_read_validators_with_enabled_validators() {
+        let validators = filter_single_read_validators(
+            ValidationLevel::High,
+            &[],
+            &[String::from("S009")],
+            &Alphabet::default(),
+            None,
+        );
+
+        assert_eq!(validators.len(), 7);
+        assert_eq!(validators[6].code(), "S009");
+
+        let validators = filter_single_read_validators(
+            ValidationLevel::Medium,
+            &[],
+            &[String::from("S009")],
+            &Alphabet::default(),
+            None,
+        );
+
+        assert!(!validators.iter().any(|v| v.code() == "S009"));
+    }
+
     #[test]
     fn test_filter_paired_read_validators() {
         let disabled_validators = Vec::new();
 
-        let validators = filter_paired_read_validators(ValidationLevel::Low, &disabled_validators);
+        let validators =
+            filter_paired_read_validators(ValidationLevel::Low, &disabled_validators, &[]);
 
         assert_eq!(validators.len(), 0);
 
-        let validators = filter_paired_read_validators(ValidationLevel::High, &disabled_validators);
+        let validators =
+            filter_paired_read_validators(ValidationLevel::High, &disabled_validators, &[]);
 
         assert_eq!(validators.len(), 1);
         assert_eq!(validators[0].name(), "NamesValidator");
@@ -204,9 +325,26 @@ mod tests {
     fn test_filter_paired_read_validators_with_disabled_validators() {
         let disabled_validators = vec![String::from("P001")];
 
-        let validators = filter_paired_read_validators(ValidationLevel::High, &disabled_validators);
+        let validators =
+            filter_paired_read_validators(ValidationLevel::High, &disabled_validators, &[]);
 
         assert_eq!(validators.len(), 0);
         assert!(!validators.iter().any(|v| v.code() == "P001"));
     }
+
+    #[test]
+    fn test_filter_paired_read_validators_with_enabled_validators() {
+        let enabled_validators = [String::from("P002")];
+
+        let validators =
+            filter_paired_read_validators(ValidationLevel::High, &[], &enabled_validators);
+
+        assert_eq!(validators.len(), 2);
+        assert_eq!(validators[1].code(), "P002");
+
+        let validators =
+            filter_paired_read_validators(ValidationLevel::Low, &[], &enabled_validators);
+
+        assert!(validators.is_empty());
+    }
 }
@@ -1,15 +1,16 @@
 //! Validators that use records from paired reads.
 
 mod names;
+mod read_numbers;
 
-pub use self::names::NamesValidator;
+pub use self::{names::NamesValidator, read_numbers::ReadNumbersValidator};
 
 use crate::{
     fastq::Record,
     validators::{Error, ValidationLevel},
 };
 
-pub trait PairedReadValidator {
+pub trait PairedReadValidator: Send + Sync {
     fn code(&self) -> &'static str;
     fn name(&self) -> &'static str;
     fn level(&self) -> ValidationLevel;
@@ -0,0 +1,165 @@
+use thiserror::Error;
+
+use crate::{
+    fastq::Record,
+    validators::{self, LineType, PairedReadValidator, ValidationLevel},
+};
+
+/// [P002] (medium) Validator to check if read 1 has read number 1 and read 2 has read number 2.
+///
+/// The read number is taken from a `/1` or `/2` name suffix or the first field of a CASAVA 1.8+
+/// description, e.g., `1:N:0:ATCACG`. Reads without a read number are not checked.
+///
+/// This validator is not enabled by default.
+pub struct ReadNumbersValidator;
+
+impl PairedReadValidator for ReadNumbersValidator {
+    fn code(&self) -> &'static str {
+        "P002"
+    }
+
+    fn name(&self) -> &'static str {
+        "ReadNumbersValidator"
+    }
+
+    fn level(&self) -> ValidationLevel {
+        ValidationLevel::Medium
+    }
+
+    fn validate(&self, r: &Record, s: &Record) -> Result<(), validators::Error> {
+        let r_read_number = read_number(r.definition());
+        let s_read_number = read_number(s.definition());
+
+        let (e, col_no) = match (r_read_number, s_read_number) {
+            (Some((b"2", col_no)), Some((b"1", _))) => (ValidationError::Swapped, col_no),
+            (Some((a, col_no)), Some((b, _))) if a == b => {
+                (ValidationError::Duplicate(lossy(a)), col_no)
+            }
+            (Some((n, col_no)), _) if n != b"1" => (ValidationError::Invalid(1, lossy(n)), col_no),
+            (_, Some((n, col_no))) if n != b"2" => (ValidationError::Invalid(2, lossy(n)), col_no),
+            _ => return Ok(()),
+        };
+
+        Err(validators::Error::new(
+            self.code(),
+            self.name(),
+            e,
+            LineType::Name,
+            Some(col_no),
+        ))
+    }
+}
+
+// Returns the read number and its 1-based column in the definition, if any.
+fn read_number(definition: &[u8]) -> Option<(&[u8], usize)> {
+    let (name, description) = match definition.iter().position(|&b| b == b' ') {
+        Some(i) => (&definition[..i], Some(i + 1)),
+        None => (definition, None),
+    };
+
+    if let Some(i) = name.iter().rposition(|&b| b == b'/') {
+        let suffix = &name[i + 1..];
+
+        if is_number(suffix) {
+            return Some((suffix, i + 2));
+        }
+    }
+
+    let start = description?;
+    let mut fields = definition[start..].splitn(3, |&b| b == b':');
+
+    match (fields.next(), fields.next(), fields.next()) {
+        (Some(n), Some(b"Y" | b"N"), Some(_)) if is_number(n) => Some((n, start + 1)),
+        _ => None,
+    }
+}
+
+fn is_number(buf: &[u8]) -> bool {
+    !buf.is_empty() && buf.iter().all(u8::is_ascii_digit)
+}
+
+fn lossy(buf: &[u8]) -> String {
+    String::from_utf8_lossy(buf).into()
+}
+
+#[derive(Debug, Error)]
+enum ValidationError {
+    #[error("swapped read numbers: read 1 has read number 2 and read 2 has read number 1")]
+    Swapped,
+    #[error("duplicate read number: both reads have read number {0}")]
+    Duplicate(String),
+    #[error("invalid read number for read {0}: expected {0}, got {1}")]
+    Invalid(u8, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code() {
+        let validator = ReadNumbersValidator;
+        assert_eq!(validator.code(), "P002");
+    }
+
+    #[test]
+    fn test_name() {
+        let validator = ReadNumbersValidator;
+        assert_eq!(validator.name(), "ReadNumbersValidator");
+    }
+
+    #[test]
+    fn test_level() {
+        let validator = ReadNumbersValidator;
+        assert_eq!(validator.level(), ValidationLevel::Medium);
+    }
+
+    #[test]
+    fn test_validate() {
+        fn t(r: &str, s: &str) -> Result<(), (String, Option<usize>)> {
+            let r = Record::new(r, "", "", "");
+            let s = Record::new(s, "", "", "");
+
+            ReadNumbersValidator
+                .validate(&r, &s)
+                .map_err(|e| (e.to_string(), e.col_no))
+        }
+
+        assert!(t("@fqlib/1", "@fqlib/2").is_ok());
+        assert!(t("@fqlib 1:N:0:ATCACG", "@fqlib 2:N:0:ATCACG").is_ok());
+        assert!(t("@fqlib/1 RG:rg0", "@fqlib/2 RG:rg0").is_ok());
+        assert!(t("@fqlib", "@fqlib").is_ok());
+        assert!(t("@fqlib 1:abc", "@fqlib 1:abc").is_ok());
+
+        assert_eq!(
+            t("@fqlib/2", "@fqlib/1"),
+            Err((
+                String::from(
+                    "swapped read numbers: read 1 has read number 2 and read 2 has read number 1"
+                ),
+                Some(8)
+            ))
+        );
+        assert_eq!(
+            t("@fqlib 1:Y:0:ATCACG", "@fqlib 1:N:0:ATCACG"),
+            Err((
+                String::from("duplicate read number: both reads have read number 1"),
+                Some(8)
+            ))
+        );
+        assert_eq!(
+            t("@fqlib/3", "@fqlib"),
+            Err((
+                String::from("invalid read number for read 1: expected 1, got 3"),
+                Some(8)
+            ))
+        );
+        assert_eq!(
+            t("@fqlib", "@fqlib 3:N:0:1"),
+            Err((
+                String::from("invalid read number for read 2: expected 2, got 3"),
+                Some(8)
+            ))
+        );
+    }
+}
@@ -1,17 +1,27 @@
 //! Validators that use records from a single read.
 
 mod alphabet;
+mod casava_header;
 mod complete;
 mod consistent_seq_qual;
 mod duplicate_name;
 mod name;
 mod plus_line;
+mod quality_encoding;
 mod quality_string;
 
 pub use self::{
-    alphabet::AlphabetValidator, complete::CompleteValidator,
-    consistent_seq_qual::ConsistentSeqQualValidator, duplicate_name::DuplicateNameValidator,
-    name::NameValidator, plus_line::PlusLineValidator, quality_string::QualityStringValidator,
+    alphabet::{Alphabet, AlphabetValidator},
+    casava_header::CasavaHeaderValidator,
+    complete::CompleteValidator,
+    consistent_seq_qual::ConsistentSeqQualValidator,
+    duplicate_name::{DuplicateNameMode, DuplicateNameValidator},
+    name::NameValidator,
+    plus_line::PlusLineValidator,
+    quality_encoding::{
+        QualityEncoding, QualityEncodingDetector, QualityEncodingMode, QualityEncodingValidator,
+    },
+    quality_string::QualityStringValidator,
 };
 
 use crate::{
@@ -19,7 +29,7 @@ use crate::{
     validators::{Error, ValidationLevel},
 };
 
-pub trait SingleReadValidator {
+pub trait SingleReadValidator: Send + Sync {
     fn code(&self) -> &'static str;
     fn name(&self) -> &'static str;
     fn level(&self) -> ValidationLevel;
@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 use thiserror::Error;
 
 use crate::{
@@ -5,6 +7,66 @@ use crate::{
     validators::{self, LineType, SingleReadValidator, ValidationLevel},
 };
 
+/// A set of valid sequence characters.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum Alphabet {
+    /// `ACGTN`, case-insensitive.
+    #[default]
+    Dna,
+    /// `ACGT`, case-insensitive.
+    DnaStrict,
+    /// `ACGT` and the IUPAC ambiguity codes `RYSWKMBDHVN`, case-insensitive.
+    DnaIupac,
+    /// `ACGUN`, case-insensitive.
+    Rna,
+    /// A custom set of characters. This is case-sensitive.
+    Custom(Vec<u8>),
+}
+
+impl Alphabet {
+    /// Returns the characters in the alphabet.
+    pub fn characters(&self) -> &[u8] {
+        match self {
+            Self::Dna => b"ACGTNacgtn",
+            Self::DnaStrict => b"ACGTacgt",
+            Self::DnaIupac => b"ACGTRYSWKMBDHVNacgtryswkmbdhvn",
+            Self::Rna => b"ACGUNacgun",
+            Self::Custom(characters) => characters,
+        }
+    }
+}
+
+impl FromStr for Alphabet {
+    type Err = &'static str;
+
+    /// Parses a preset name (`dna`, `dna-strict`, `dna-iupac`, or `rna`) or a list of
+    /// characters.
+    ///
+    /// Preset names are case-insensitive, so they take precedence over a list of characters with
+    /// the same letters, e.g., `DNA`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const PRESETS: [(&str, Alphabet); 4] = [
+            ("dna", Alphabet::Dna),
+            ("dna-strict", Alphabet::DnaStrict),
+            ("dna-iupac", Alphabet::DnaIupac),
+            ("rna", Alphabet::Rna),
+        ];
+
+        if let Some((_, alphabet)) = PRESETS
+            .into_iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+        {
+            return Ok(alphabet);
+        }
+
+        match s {
+            "" => Err("empty alphabet"),
+            _ if s.bytes().all(|b| b.is_ascii_graphic()) => Ok(Self::Custom(s.bytes().collect())),
+            _ => Err("invalid alphabet character"),
+        }
+    }
+}
+
 /// [S002] (medium) Validator to check if all the characters in the sequence line are included in a
 /// given character set.
 pub struct AlphabetValidator {
@@ -56,7 +118,13 @@ impl SingleReadValidator for AlphabetValidator {
 impl Default for AlphabetValidator {
     /// Creates a validator with the alphabet "ACGTN", case-insensitive.
     fn default() -> Self {
-        Self::new(b"ACGTNacgtn")
+        Self::from(&Alphabet::default())
+    }
+}
+
+impl From<&Alphabet> for AlphabetValidator {
+    fn from(alphabet: &Alphabet) -> Self {
+        Self::new(alphabet.characters())
     }
 }
 
@@ -68,6 +136,25 @@ struct ValidationError(char);
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_alphabet_from_str() {
+        assert_eq!("dna".parse(), Ok(Alphabet::Dna));
+        assert_eq!("dna-strict".parse(), Ok(Alphabet::DnaStrict));
+        assert_eq!("dna-iupac".parse(), Ok(Alphabet::DnaIupac));
+        assert_eq!("rna".parse(), Ok(Alphabet::Rna));
+        assert_eq!("DNA".parse(), Ok(Alphabet::Dna));
+        assert_eq!("Dna-Strict".parse(), Ok(Alphabet::DnaStrict));
+        assert_eq!("DNA-IUPAC".parse(), Ok(Alphabet::DnaIupac));
+        assert_eq!("RNA".parse(), Ok(Alphabet::Rna));
+        assert_eq!("ACGT".parse(), Ok(Alphabet::Custom(b"ACGT".to_vec())));
+
+        assert_eq!("".parse::<Alphabet>(), Err("empty alphabet"));
+        assert_eq!(
+            "AC GT".parse::<Alphabet>(),
+            Err("invalid alphabet character")
+        );
+    }
+
     #[test]
     fn test_new() {
         let validator = AlphabetValidator::new(b"abc");
@@ -104,4 +191,33 @@ mod tests {
         let record = Record::new("", "fqlib", "", "");
         assert!(validator.validate(&record).is_err());
     }
+
+    #[test]
+    fn test_validate_with_presets() {
+        let record = Record::new("", "ACGUN", "", "");
+        assert!(
+            AlphabetValidator::from(&Alphabet::Rna)
+                .validate(&record)
+                .is_ok()
+        );
+        assert!(
+            AlphabetValidator::from(&Alphabet::Dna)
+                .validate(&record)
+                .is_err()
+        );
+
+        let record = Record::new("", "ACGTRYkm", "", "");
+        assert!(
+            AlphabetValidator::from(&Alphabet::DnaIupac)
+                .validate(&record)
+                .is_ok()
+        );
+
+        let record = Record::new("", "ACGTN", "", "");
+        assert!(
+            AlphabetValidator::from(&Alphabet::DnaStrict)
+                .validate(&record)
+                .is_err()
+        );
+    }
 }
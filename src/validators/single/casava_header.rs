@@ -0,0 +1,364 @@
+use std::sync::OnceLock;
+
+use thiserror::Error;
+
+use crate::{
+    fastq::Record,
+    validators::{self, LineType, SingleReadValidator, ValidationLevel},
+};
+
+const NAME_FIELD_NAMES: [&str; 7] = [
+    "instrument",
+    "run number",
+    "flowcell",
+    "lane",
+    "tile",
+    "x-position",
+    "y-position",
+];
+
+const DESCRIPTION_FIELD_NAMES: [&str; 4] =
+    ["read number", "filtered flag", "control number", "index"];
+
+/// [S009] (high) Validator to check if the definition line is a CASAVA 1.8+ header.
+///
+/// The header has the form `@instrument:run:flowcell:lane:tile:x:y read:filtered:control:index`,
+/// optionally with a UMI as an eighth name field. The run number, lane, tile, position, read
+/// number, and control number must be numeric, and the filtered flag must be `Y` or `N`.
+///
+/// This also checks that all records have the same instrument and flowcell as the first record.
+/// If the first record does not have a valid header, this check is skipped.
+///
+/// This validator is not enabled by default.
+#[derive(Default)]
+pub struct CasavaHeaderValidator {
+    reference: OnceLock<Option<(Vec<u8>, Vec<u8>)>>,
+}
+
+impl CasavaHeaderValidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SingleReadValidator for CasavaHeaderValidator {
+    fn code(&self) -> &'static str {
+        "S009"
+    }
+
+    fn name(&self) -> &'static str {
+        "CasavaHeaderValidator"
+    }
+
+    fn level(&self) -> ValidationLevel {
+        ValidationLevel::High
+    }
+
+    fn validate(&self, r: &Record) -> Result<(), validators::Error> {
+        let result = parse_header(r.definition());
+
+        let reference = self.reference.get_or_init(|| {
+            result.as_ref().ok().map(|header| {
+                (
+                    header.instrument.value.to_vec(),
+                    header.flowcell.value.to_vec(),
+                )
+            })
+        });
+
+        let header = result.map_err(|(e, col_no)| self.build_error(e, col_no))?;
+
+        if let Some((instrument, flowcell)) = reference {
+            if header.instrument.value != instrument.as_slice() {
+                return Err(self.build_error(
+                    ValidationError::MixedInstruments(
+                        lossy(instrument),
+                        lossy(header.instrument.value),
+                    ),
+                    header.instrument.col_no,
+                ));
+            }
+
+            if header.flowcell.value != flowcell.as_slice() {
+                return Err(self.build_error(
+                    ValidationError::MixedFlowcells(lossy(flowcell), lossy(header.flowcell.value)),
+                    header.flowcell.col_no,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CasavaHeaderValidator {
+    fn build_error(&self, e: ValidationError, col_no: usize) -> validators::Error {
+        validators::Error::new(self.code(), self.name(), e, LineType::Name, Some(col_no))
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Field<'a> {
+    value: &'a [u8],
+    col_no: usize,
+}
+
+struct Header<'a> {
+    instrument: Field<'a>,
+    flowcell: Field<'a>,
+}
+
+fn parse_header(definition: &[u8]) -> Result<Header<'_>, (ValidationError, usize)> {
+    let Some(name) = definition.strip_prefix(b"@") else {
+        return Err((ValidationError::MissingPrefix, 1));
+    };
+
+    let (name, description) = match name.iter().position(|&b| b == b' ') {
+        Some(i) => (&name[..i], Some((&name[i + 1..], i + 3))),
+        None => (name, None),
+    };
+
+    let name_fields = split_fields(name, 2);
+
+    if !(NAME_FIELD_NAMES.len()..=NAME_FIELD_NAMES.len() + 1).contains(&name_fields.len()) {
+        return Err((ValidationError::InvalidNameFieldCount(name_fields.len()), 2));
+    }
+
+    for (i, (field, field_name)) in name_fields.iter().zip(NAME_FIELD_NAMES).enumerate() {
+        match i {
+            0 | 2 => require_present(field, field_name)?,
+            _ => require_numeric(field, field_name)?,
+        }
+    }
+
+    let Some((description, col_no)) = description else {
+        return Err((ValidationError::MissingDescription, definition.len() + 1));
+    };
+
+    let description_fields = split_fields(description, col_no);
+
+    if description_fields.len() != DESCRIPTION_FIELD_NAMES.len() {
+        return Err((
+            ValidationError::InvalidDescriptionFieldCount(description_fields.len()),
+            col_no,
+        ));
+    }
+
+    require_numeric(&description_fields[0], DESCRIPTION_FIELD_NAMES[0])?;
+
+    let filtered_flag = &description_fields[1];
+
+    if !matches!(filtered_flag.value, b"Y" | b"N") {
+        return Err((
+            ValidationError::InvalidFilteredFlag(lossy(filtered_flag.value)),
+            filtered_flag.col_no,
+        ));
+    }
+
+    require_numeric(&description_fields[2], DESCRIPTION_FIELD_NAMES[2])?;
+
+    Ok(Header {
+        instrument: name_fields[0],
+        flowcell: name_fields[2],
+    })
+}
+
+fn split_fields(buf: &[u8], col_no: usize) -> Vec<Field<'_>> {
+    let mut col_no = col_no;
+
+    buf.split(|&b| b == b':')
+        .map(|value| {
+            let field = Field { value, col_no };
+            col_no += value.len() + 1;
+            field
+        })
+        .collect()
+}
+
+fn require_present(field: &Field<'_>, name: &'static str) -> Result<(), (ValidationError, usize)> {
+    if field.value.is_empty() {
+        Err((ValidationError::MissingField(name), field.col_no))
+    } else {
+        Ok(())
+    }
+}
+
+fn require_numeric(field: &Field<'_>, name: &'static str) -> Result<(), (ValidationError, usize)> {
+    require_present(field, name)?;
+
+    if field.value.iter().all(u8::is_ascii_digit) {
+        Ok(())
+    } else {
+        Err((
+            ValidationError::NonNumericField(name, lossy(field.value)),
+            field.col_no,
+        ))
+    }
+}
+
+fn lossy(buf: &[u8]) -> String {
+    String::from_utf8_lossy(buf).into()
+}
+
+#[derive(Debug, Error)]
+enum ValidationError {
+    #[error("missing @ prefix")]
+    MissingPrefix,
+    #[error("invalid CASAVA name: expected 7 or 8 fields, got {0}")]
+    InvalidNameFieldCount(usize),
+    #[error("missing CASAVA description")]
+    MissingDescription,
+    #[error("invalid CASAVA description: expected 4 fields, got {0}")]
+    InvalidDescriptionFieldCount(usize),
+    #[error("missing CASAVA {0}")]
+    MissingField(&'static str),
+    #[error("invalid CASAVA {0}: expected a number, got '{1}'")]
+    NonNumericField(&'static str, String),
+    #[error("invalid CASAVA filtered flag: expected Y or N, got '{0}'")]
+    InvalidFilteredFlag(String),
+    #[error("mixed instruments: expected '{0}', got '{1}'")]
+    MixedInstruments(String, String),
+    #[error("mixed flowcells: expected '{0}', got '{1}'")]
+    MixedFlowcells(String, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFINITION: &str = "@EAS139:136:FC706VJ:2:2104:15343:197393 1:Y:18:ATCACG";
+
+    #[test]
+    fn test_code() {
+        let validator = CasavaHeaderValidator::new();
+        assert_eq!(validator.code(), "S009");
+    }
+
+    #[test]
+    fn test_name() {
+        let validator = CasavaHeaderValidator::new();
+        assert_eq!(validator.name(), "CasavaHeaderValidator");
+    }
+
+    #[test]
+    fn test_level() {
+        let validator = CasavaHeaderValidator::new();
+        assert_eq!(validator.level(), ValidationLevel::High);
+    }
+
+    #[test]
+    fn test_validate() {
+        fn t(definition: &str) -> Result<(), (String, Option<usize>)> {
+            let validator = CasavaHeaderValidator::new();
+            let record = Record::new(definition, "", "", "");
+
+            validator
+                .validate(&record)
+                .map_err(|e| (e.to_string(), e.col_no))
+        }
+
+        assert!(t(DEFINITION).is_ok());
+        assert!(t("@EAS139:136:FC706VJ:2:2104:15343:197393:ACGTACGT 2:N:0:").is_ok());
+
+        assert_eq!(
+            t("EAS139:136:FC706VJ:2:2104:15343:197393 1:Y:18:ATCACG"),
+            Err((String::from("missing @ prefix"), Some(1)))
+        );
+        assert_eq!(
+            t("@EAS139:136:FC706VJ:2:2104 1:Y:18:ATCACG"),
+            Err((
+                String::from("invalid CASAVA name: expected 7 or 8 fields, got 5"),
+                Some(2)
+            ))
+        );
+        assert_eq!(
+            t("@EAS139:136:FC706VJ:2:2104:15343:197393"),
+            Err((String::from("missing CASAVA description"), Some(40)))
+        );
+        assert_eq!(
+            t("@EAS139:136:FC706VJ:2:2104:15343:197393 1:Y:18"),
+            Err((
+                String::from("invalid CASAVA description: expected 4 fields, got 3"),
+                Some(41)
+            ))
+        );
+        assert_eq!(
+            t("@EAS139:136::2:2104:15343:197393 1:Y:18:ATCACG"),
+            Err((String::from("missing CASAVA flowcell"), Some(13)))
+        );
+        assert_eq!(
+            t("@EAS139:136:FC706VJ:L2:2104:15343:197393 1:Y:18:ATCACG"),
+            Err((
+                String::from("invalid CASAVA lane: expected a number, got 'L2'"),
+                Some(21)
+            ))
+        );
+        assert_eq!(
+            t("@EAS139:136:FC706VJ:2:tile:15343:197393 1:Y:18:ATCACG"),
+            Err((
+                String::from("invalid CASAVA tile: expected a number, got 'tile'"),
+                Some(23)
+            ))
+        );
+        assert_eq!(
+            t("@EAS139:136:FC706VJ:2:2104:15343:197393 1:y:18:ATCACG"),
+            Err((
+                String::from("invalid CASAVA filtered flag: expected Y or N, got 'y'"),
+                Some(43)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_validate_with_mixed_headers() {
+        let validator = CasavaHeaderValidator::new();
+
+        let record = Record::new(DEFINITION, "", "", "");
+        assert!(validator.validate(&record).is_ok());
+
+        let record = Record::new(
+            "@EAS140:136:FC706VJ:2:2104:15343:197393 1:Y:18:ATCACG",
+            "",
+            "",
+            "",
+        );
+        let err = validator.validate(&record).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "mixed instruments: expected 'EAS139', got 'EAS140'"
+        );
+        assert_eq!(err.col_no, Some(2));
+
+        let record = Record::new(
+            "@EAS139:136:FC706VK:2:2104:15343:197393 1:Y:18:ATCACG",
+            "",
+            "",
+            "",
+        );
+        let err = validator.validate(&record).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "mixed flowcells: expected 'FC706VJ', got 'FC706VK'"
+        );
+        assert_eq!(err.col_no, Some(13));
+    }
+
+    #[test]
+    fn test_validate_with_invalid_first_header() {
+        let validator = CasavaHeaderValidator::new();
+
+        let record = Record::new("@fqlib", "", "", "");
+        assert!(validator.validate(&record).is_err());
+
+        let record = Record::new(DEFINITION, "", "", "");
+        assert!(validator.validate(&record).is_ok());
+
+        let record = Record::new(
+            "@EAS140:136:FC706VJ:2:2104:15343:197393 1:Y:18:ATCACG",
+            "",
+            "",
+            "",
+        );
+        assert!(validator.validate(&record).is_ok());
+    }
+}
@@ -1,9 +1,13 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+};
 
+use rapidhash::v3::rapidhash_v3;
 use thiserror::Error;
 
 use crate::{
-    collections::ScalableBloomFilter,
+    collections::{ExternalHashSorter, ScalableBloomFilter},
     fastq::Record,
     validators::{self, LineType, SingleReadValidatorMut, ValidationLevel},
 };
@@ -11,45 +15,133 @@ use crate::{
 const FALSE_POSITIVE_PROBABILITY: f64 = 0.0001;
 const INITIAL_CAPACITY: usize = 10_000_000;
 
+// The number of name hashes held in memory (128 MiB) before a sorted run is spilled to disk.
+const RUN_CAPACITY: usize = 1 << 24;
+
+/// How possible duplicate names are found.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum DuplicateNameMode {
+    /// Use an in-memory Bloom filter.
+    Bloom,
+    /// Use sorted runs of name hashes spilled to disk.
+    ///
+    /// Memory for the name hashes is bounded, but the possible duplicates are held in memory.
+    Exact,
+}
+
 /// [S007] (high) Validator to check if all record names are unique.
 ///
-/// The implementation of this validator uses a Bloom filter, a probabilistic data structure.
-/// Because of this, it must be used in two passes: the first to add all names to the set
-/// ([`insert`]), which may or may not hit duplicates; and the second, checking that list of
-/// possible duplicates ([`validate`]).
+/// This validator must be used in two passes: the first to add all names to the set
+/// ([`insert`]), which finds possible duplicates; and the second, checking those possible
+/// duplicates against the actual names ([`validate`]). [`finish`] must be called between the
+/// passes.
+///
+/// Possible duplicates are found using either a Bloom filter, a probabilistic data structure that
+/// grows with the number of names, or, in exact mode, an external sort of 64-bit name hashes.
+/// Either way, the second pass compares full names, so only actual duplicates are reported.
+///
+/// In both modes, the possible duplicates are held in memory and grow with the number of
+/// duplicated names.
 ///
 /// [`insert`]: #method.insert
+/// [`finish`]: #method.finish
 /// [`validate`]: #method.validate
 pub struct DuplicateNameValidator {
-    filter: ScalableBloomFilter,
+    index: Index,
     possible_duplicates: HashMap<Vec<u8>, u8>,
 }
 
+enum Index {
+    Bloom(ScalableBloomFilter),
+    Exact {
+        sorter: Option<ExternalHashSorter>,
+        duplicate_hashes: HashSet<u64>,
+    },
+}
+
 impl DuplicateNameValidator {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Creates a validator that finds possible duplicates using the given mode.
+    pub fn with_mode(mode: DuplicateNameMode) -> Self {
+        Self::with_mode_and_run_capacity(mode, RUN_CAPACITY)
+    }
+
+    fn with_mode_and_run_capacity(mode: DuplicateNameMode, run_capacity: usize) -> Self {
+        let index = match mode {
+            DuplicateNameMode::Bloom => Index::Bloom(ScalableBloomFilter::new(
+                FALSE_POSITIVE_PROBABILITY,
+                INITIAL_CAPACITY,
+            )),
+            DuplicateNameMode::Exact => Index::Exact {
+                sorter: Some(ExternalHashSorter::new(run_capacity)),
+                duplicate_hashes: HashSet::new(),
+            },
+        };
+
+        Self {
+            index,
+            possible_duplicates: HashMap::new(),
+        }
+    }
 }
 
 impl DuplicateNameValidator {
     /// Adds a record name to the set.
     ///
-    /// This also records possible duplicates to be used in the validation pass.
-    pub fn insert(&mut self, r: &Record) {
+    /// This also records possible duplicates to be used in the validation pass. In exact mode,
+    /// this can fail when spilling to disk.
+    pub fn insert(&mut self, r: &Record) -> io::Result<()> {
         let name = r.name();
 
-        if self.filter.contains_or_insert(name) {
-            self.possible_duplicates.insert(name.to_vec(), 0);
+        match &mut self.index {
+            Index::Bloom(filter) => {
+                if filter.contains_or_insert(name) {
+                    self.possible_duplicates.insert(name.to_vec(), 0);
+                }
+            }
+            Index::Exact { sorter, .. } => {
+                if let Some(sorter) = sorter {
+                    sorter.insert(rapidhash_v3(name))?;
+                }
+            }
         }
+
+        Ok(())
+    }
+
+    /// Ends the insert pass.
+    ///
+    /// In exact mode, this merges the sorted runs to find possible duplicates.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if let Index::Exact {
+            sorter,
+            duplicate_hashes,
+        } = &mut self.index
+            && let Some(sorter) = sorter.take()
+        {
+            *duplicate_hashes = sorter.duplicates()?;
+        }
+
+        Ok(())
     }
 
     /// Returns whether there are possible duplicates.
     ///
-    /// This is only useful if [`insert`] was previously called for all names.
+    /// This is only useful if [`insert`] was previously called for all names and the pass was
+    /// [`finish`]ed.
     ///
     /// [`insert`]: #method.insert
+    /// [`finish`]: #method.finish
     pub fn is_empty(&self) -> bool {
-        self.possible_duplicates.is_empty()
+        match &self.index {
+            Index::Bloom(_) => self.possible_duplicates.is_empty(),
+            Index::Exact {
+                duplicate_hashes, ..
+            } => duplicate_hashes.is_empty(),
+        }
     }
 }
 
@@ -67,7 +159,22 @@ impl SingleReadValidatorMut for DuplicateNameValidator {
     }
 
     fn validate(&mut self, r: &Record) -> Result<(), validators::Error> {
-        if let Some(count) = self.possible_duplicates.get_mut(r.name()) {
+        let name = r.name();
+
+        let count = match &self.index {
+            Index::Bloom(_) => self.possible_duplicates.get_mut(name),
+            Index::Exact {
+                duplicate_hashes, ..
+            } => {
+                if duplicate_hashes.contains(&rapidhash_v3(name)) {
+                    Some(self.possible_duplicates.entry(name.to_vec()).or_default())
+                } else {
+                    None
+                }
+            }
+        };
+
+        if let Some(count) = count {
             if *count >= 1 {
                 return Err(validators::Error::new(
                     self.code(),
@@ -87,10 +194,7 @@ impl SingleReadValidatorMut for DuplicateNameValidator {
 
 impl Default for DuplicateNameValidator {
     fn default() -> Self {
-        Self {
-            filter: ScalableBloomFilter::new(FALSE_POSITIVE_PROBABILITY, INITIAL_CAPACITY),
-            possible_duplicates: HashMap::new(),
-        }
+        Self::with_mode(DuplicateNameMode::Bloom)
     }
 }
 
@@ -103,10 +207,10 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_insert() {
+    fn test_insert() -> io::Result<()> {
         let mut validator = DuplicateNameValidator::new();
         let record = Record::new("@fqlib:1", "", "", "");
-        validator.insert(&record);
+        validator.insert(&record)
     }
 
     #[test]
@@ -134,20 +238,52 @@ mod tests {
     }
 
     #[test]
-    fn test_validate() {
-        let mut validator = DuplicateNameValidator::new();
-
+    fn test_validate() -> io::Result<()> {
         let r = Record::new("@fqlib:1", "", "", "");
         let s = Record::new("@fqlib:2", "", "", "");
 
-        // pass 1
-        validator.insert(&r);
-        validator.insert(&s);
-        validator.insert(&s);
+        let validators = [
+            DuplicateNameValidator::new(),
+            DuplicateNameValidator::with_mode(DuplicateNameMode::Exact),
+            DuplicateNameValidator::with_mode_and_run_capacity(DuplicateNameMode::Exact, 1),
+        ];
+
+        for mut validator in validators {
+            // pass 1
+            validator.insert(&r)?;
+            validator.insert(&s)?;
+            validator.insert(&s)?;
+            validator.finish()?;
+
+            assert!(!validator.is_empty());
+
+            // pass 2
+            assert!(validator.validate(&r).is_ok());
+            assert!(validator.validate(&s).is_ok());
+            assert!(validator.validate(&s).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_with_exact_mode_and_no_duplicates() -> io::Result<()> {
+        let mut validator =
+            DuplicateNameValidator::with_mode_and_run_capacity(DuplicateNameMode::Exact, 2);
+
+        let records: Vec<_> = (0..5)
+            .map(|i| Record::new(format!("@fqlib:{i}"), "", "", ""))
+            .collect();
 
-        // pass 2
-        assert!(validator.validate(&r).is_ok());
-        assert!(validator.validate(&s).is_ok());
-        assert!(validator.validate(&s).is_err());
+        for record in &records {
+            validator.insert(record)?;
+        }
+
+        validator.finish()?;
+
+        assert!(validator.is_empty());
+        assert!(records.iter().all(|r| validator.validate(r).is_ok()));
+
+        Ok(())
     }
 }
@@ -0,0 +1,241 @@
+use std::{
+    fmt,
+    ops::RangeInclusive,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use thiserror::Error;
+
+use crate::{
+    fastq::Record,
+    validators::{self, LineType, SingleReadValidator, ValidationLevel},
+};
+
+/// A quality score encoding.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum QualityEncoding {
+    /// Phred scores offset by 33 (Sanger and Illumina 1.8+).
+    Phred33,
+    /// Phred scores offset by 64 (Illumina 1.3 to 1.7).
+    Phred64,
+    /// Solexa scores offset by 64 (Solexa and Illumina 1.0).
+    Solexa,
+}
+
+impl QualityEncoding {
+    const ALL: [Self; 3] = [Self::Phred33, Self::Phred64, Self::Solexa];
+
+    /// Returns the range of valid characters for this encoding.
+    ///
+    /// Phred+33 scores are 0 to 93, Phred+64 scores are 0 to 62, and Solexa+64 scores are -5 to
+    /// 62.
+    pub fn range(self) -> RangeInclusive<u8> {
+        match self {
+            Self::Phred33 => b'!'..=b'~',
+            Self::Phred64 => b'@'..=b'~',
+            Self::Solexa => b';'..=b'~',
+        }
+    }
+}
+
+impl fmt::Display for QualityEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Phred33 => f.write_str("Phred+33"),
+            Self::Phred64 => f.write_str("Phred+64"),
+            Self::Solexa => f.write_str("Solexa+64"),
+        }
+    }
+}
+
+/// How quality score encodings are checked.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum QualityEncodingMode {
+    /// Report which encodings are consistent with the observed quality scores.
+    Auto,
+    /// Phred+33.
+    Phred33,
+    /// Phred+64.
+    Phred64,
+    /// Solexa+64.
+    Solexa,
+}
+
+impl QualityEncodingMode {
+    /// Returns the declared encoding, or `None` if the encoding is detected.
+    pub fn encoding(self) -> Option<QualityEncoding> {
+        match self {
+            Self::Auto => None,
+            Self::Phred33 => Some(QualityEncoding::Phred33),
+            Self::Phred64 => Some(QualityEncoding::Phred64),
+            Self::Solexa => Some(QualityEncoding::Solexa),
+        }
+    }
+}
+
+/// [S008] (medium) Validator to check if all the characters in the quality line are valid for a
+/// quality score encoding.
+pub struct QualityEncodingValidator {
+    encoding: QualityEncoding,
+}
+
+impl QualityEncodingValidator {
+    pub fn new(encoding: QualityEncoding) -> Self {
+        Self { encoding }
+    }
+}
+
+impl SingleReadValidator for QualityEncodingValidator {
+    fn code(&self) -> &'static str {
+        "S008"
+    }
+
+    fn name(&self) -> &'static str {
+        "QualityEncodingValidator"
+    }
+
+    fn level(&self) -> ValidationLevel {
+        ValidationLevel::Medium
+    }
+
+    fn validate(&self, r: &Record) -> Result<(), validators::Error> {
+        let range = self.encoding.range();
+
+        for (i, &b) in r.quality_scores().iter().enumerate() {
+            if !range.contains(&b) {
+                return Err(validators::Error::new(
+                    self.code(),
+                    self.name(),
+                    ValidationError(char::from(b), self.encoding),
+                    LineType::Quality,
+                    Some(i + 1),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("invalid {1} quality score character: '{0}'")]
+struct ValidationError(char, QualityEncoding);
+
+/// Tracks the range of observed quality score characters to find consistent encodings.
+///
+/// This can be shared between threads.
+pub struct QualityEncodingDetector {
+    min: AtomicU8,
+    max: AtomicU8,
+}
+
+impl QualityEncodingDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the quality scores of a record to the observed range.
+    pub fn observe(&self, r: &Record) {
+        let scores = r.quality_scores();
+
+        if let (Some(&min), Some(&max)) = (scores.iter().min(), scores.iter().max()) {
+            self.min.fetch_min(min, Ordering::Relaxed);
+            self.max.fetch_max(max, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the range of observed quality score characters, if any were observed.
+    pub fn range(&self) -> Option<RangeInclusive<u8>> {
+        let min = self.min.load(Ordering::Relaxed);
+        let max = self.max.load(Ordering::Relaxed);
+        (min <= max).then_some(min..=max)
+    }
+
+    /// Returns the encodings that are consistent with the observed range.
+    ///
+    /// If nothing was observed, all encodings are consistent.
+    pub fn consistent_encodings(&self) -> Vec<QualityEncoding> {
+        QualityEncoding::ALL
+            .into_iter()
+            .filter(|encoding| {
+                self.range().is_none_or(|range| {
+                    let valid_range = encoding.range();
+                    valid_range.contains(range.start()) && valid_range.contains(range.end())
+                })
+            })
+            .collect()
+    }
+}
+
+impl Default for QualityEncodingDetector {
+    fn default() -> Self {
+        Self {
+            min: AtomicU8::new(u8::MAX),
+            max: AtomicU8::new(u8::MIN),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code() {
+        let validator = QualityEncodingValidator::new(QualityEncoding::Phred33);
+        assert_eq!(validator.code(), "S008");
+    }
+
+    #[test]
+    fn test_name() {
+        let validator = QualityEncodingValidator::new(QualityEncoding::Phred33);
+        assert_eq!(validator.name(), "QualityEncodingValidator");
+    }
+
+    #[test]
+    fn test_level() {
+        let validator = QualityEncodingValidator::new(QualityEncoding::Phred33);
+        assert_eq!(validator.level(), ValidationLevel::Medium);
+    }
+
+    #[test]
+    fn test_validate() {
+        let record = Record::new("", "", "", "!5IJ");
+        let validator = QualityEncodingValidator::new(QualityEncoding::Phred33);
+        assert!(validator.validate(&record).is_ok());
+        let validator = QualityEncodingValidator::new(QualityEncoding::Phred64);
+        assert!(validator.validate(&record).is_err());
+
+        let record = Record::new("", "", "", ";@Th");
+        let validator = QualityEncodingValidator::new(QualityEncoding::Solexa);
+        assert!(validator.validate(&record).is_ok());
+        let validator = QualityEncodingValidator::new(QualityEncoding::Phred64);
+        let err = validator.validate(&record).unwrap_err();
+        assert_eq!(err.col_no, Some(1));
+        assert_eq!(
+            err.to_string(),
+            "invalid Phred+64 quality score character: ';'"
+        );
+    }
+
+    #[test]
+    fn test_detector() {
+        let detector = QualityEncodingDetector::new();
+        assert!(detector.range().is_none());
+        assert_eq!(detector.consistent_encodings(), QualityEncoding::ALL);
+
+        detector.observe(&Record::new("", "", "", "Th"));
+        assert_eq!(detector.range(), Some(b'T'..=b'h'));
+        assert_eq!(detector.consistent_encodings(), QualityEncoding::ALL);
+
+        detector.observe(&Record::new("", "", "", ";"));
+        assert_eq!(
+            detector.consistent_encodings(),
+            [QualityEncoding::Phred33, QualityEncoding::Solexa]
+        );
+
+        detector.observe(&Record::new("", "", "", "5"));
+        assert_eq!(detector.range(), Some(b'5'..=b'h'));
+        assert_eq!(detector.consistent_encodings(), [QualityEncoding::Phred33]);
+    }
+}